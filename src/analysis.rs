@@ -0,0 +1,314 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use num_complex::Complex;
+
+use crate::fft::{fft, ifft};
+
+/// Computes the analytic signal `x + j * H(x)` of a real signal.
+pub fn analytic_signal(signal: &[f64]) -> Vec<Complex<f64>> {
+    let n = signal.len();
+    let mut spectrum: Vec<Complex<f64>> = signal.iter().map(|x| Complex::from(*x)).collect();
+    fft(&mut spectrum);
+
+    // Keep DC (and Nyquist for even lengths), double the positive frequencies and drop the
+    // negative ones.
+    for (k, bin) in spectrum.iter_mut().enumerate().skip(1) {
+        if 2 * k < n {
+            *bin *= 2.0;
+        } else if 2 * k > n {
+            *bin = Complex::ZERO;
+        }
+    }
+
+    ifft(&mut spectrum);
+    spectrum
+}
+
+/// Mean phase vector length between two signals: 1.0 means phase-locked, 0.0 random phase.
+pub fn phase_coherence(sig1: &[f64], sig2: &[f64]) -> f64 {
+    let n = sig1.len().min(sig2.len());
+    if n == 0 {
+        return 0.0;
+    }
+
+    let analytic1 = analytic_signal(&sig1[..n]);
+    let analytic2 = analytic_signal(&sig2[..n]);
+
+    let sum: Complex<f64> = analytic1
+        .iter()
+        .zip(analytic2.iter())
+        .map(|(a, b)| Complex::from_polar(1.0, a.arg() - b.arg()))
+        .sum();
+
+    (sum / n as f64).norm()
+}
+
+/// Signal-to-noise ratio in dB of `test` against `reference`, treating their difference as noise.
+pub fn snr_db(reference: &[f64], test: &[f64]) -> f64 {
+    let signal_power: f64 = reference.iter().map(|x| x * x).sum();
+    let noise_power: f64 = reference
+        .iter()
+        .zip(test.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum();
+    if noise_power == 0.0 {
+        return f64::INFINITY;
+    }
+
+    10.0 * (signal_power / noise_power).log10()
+}
+
+/// Half-wave rectified spectral flux `Σ max(0, |X_curr[k]| - |X_prev[k]|)` between two magnitude
+/// spectra.
+pub fn spectral_flux(current_frame: &[f64], previous_frame: &[f64]) -> f64 {
+    current_frame
+        .iter()
+        .zip(previous_frame.iter())
+        .map(|(current, previous)| (current - previous).max(0.0))
+        .sum()
+}
+
+/// Indices of local maxima that exceed `threshold`.
+pub fn pick_peaks(values: &[f64], threshold: f64) -> Vec<usize> {
+    (0..values.len())
+        .filter(|&i| {
+            let left = if i > 0 { values[i - 1] } else { f64::MIN };
+            let right = values.get(i + 1).copied().unwrap_or(f64::MIN);
+            values[i] > threshold && values[i] > left && values[i] >= right
+        })
+        .collect()
+}
+
+/// Frequency range `(lower_hz, upper_hz)` that holds `power_fraction` of the power in the magnitude
+/// spectrum `bins`, trimming equal amounts of power from both ends.
+pub fn signal_bandwidth(bins: &[f64], freq_resolution: f64, power_fraction: f64) -> (f64, f64) {
+    let total_power: f64 = bins.iter().map(|x| x * x).sum();
+    if total_power == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let tail = (1.0 - power_fraction.clamp(0.0, 1.0)) / 2.0 * total_power;
+    let mut cumulative = 0.0;
+    let mut lower = None;
+    let mut upper = bins.len().saturating_sub(1);
+    for (k, bin) in bins.iter().enumerate() {
+        cumulative += bin * bin;
+        if lower.is_none() && cumulative > tail {
+            lower = Some(k);
+        }
+        if cumulative >= total_power - tail {
+            upper = k;
+            break;
+        }
+    }
+
+    (
+        lower.unwrap_or(0) as f64 * freq_resolution,
+        upper as f64 * freq_resolution,
+    )
+}
+
+/// Splits a signal into its instantaneous envelope (AM) and instantaneous frequency deviation
+/// from the mean frequency in Hz (FM), both derived from the analytic signal.
+pub fn am_fm_decompose(signal: &[f64], sample_rate: f64) -> (Vec<f64>, Vec<f64>) {
+    let analytic = analytic_signal(signal);
+    let envelope = analytic.iter().map(|x| x.norm()).collect::<Vec<_>>();
+
+    // Phase differences of consecutive samples are already wrapped to (-π, π].
+    let mut frequency = analytic
+        .windows(2)
+        .map(|w| (w[1] * w[0].conj()).arg() * sample_rate / (2.0 * PI))
+        .collect::<Vec<_>>();
+    if let Some(last) = frequency.last().copied() {
+        frequency.push(last);
+    }
+    let mean = frequency.iter().sum::<f64>() / frequency.len().max(1) as f64;
+    frequency.iter_mut().for_each(|f| *f -= mean);
+
+    (envelope, frequency)
+}
+
+/// Sample entropy `-ln(A / B)`, where `B` counts pairs of length-`m` templates within Chebyshev
+/// distance `r` and `A` the same for length `m + 1`. Returns infinity if no pair matches.
+pub fn sample_entropy(signal: &[f64], m: usize, r: f64) -> f64 {
+    if signal.len() <= m + 1 {
+        return f64::INFINITY;
+    }
+
+    let num_templates = signal.len() - m;
+    let mut matches_m = 0usize;
+    let mut matches_m1 = 0usize;
+    for i in 0..num_templates {
+        for j in i + 1..num_templates {
+            let within = (0..m).all(|k| (signal[i + k] - signal[j + k]).abs() <= r);
+            if within {
+                matches_m += 1;
+                if (signal[i + m] - signal[j + m]).abs() <= r {
+                    matches_m1 += 1;
+                }
+            }
+        }
+    }
+
+    if matches_m == 0 || matches_m1 == 0 {
+        return f64::INFINITY;
+    }
+    -(matches_m1 as f64 / matches_m as f64).ln()
+}
+
+/// Shannon entropy in bits of the ordinal patterns of `order` samples spaced `delay` apart.
+pub fn permutation_entropy(signal: &[f64], order: usize, delay: usize) -> f64 {
+    let span = order.saturating_sub(1) * delay;
+    if order == 0 || signal.len() <= span {
+        return 0.0;
+    }
+
+    let mut counts: HashMap<Vec<usize>, usize> = HashMap::new();
+    for start in 0..signal.len() - span {
+        let mut pattern = (0..order).collect::<Vec<_>>();
+        pattern.sort_by(|a, b| signal[start + a * delay].total_cmp(&signal[start + b * delay]));
+        *counts.entry(pattern).or_default() += 1;
+    }
+
+    let total = (signal.len() - span) as f64;
+    -counts
+        .values()
+        .map(|count| {
+            let p = *count as f64 / total;
+            p * p.log2()
+        })
+        .sum::<f64>()
+}
+
+/// Magnitude-weighted histogram of phase angles over `num_bins` equal sectors of [-π, π).
+///
+/// Only entries whose magnitude is above `threshold` are counted.
+pub fn polar_histogram(
+    phases: &[f64],
+    num_bins: usize,
+    magnitudes: &[f64],
+    threshold: f64,
+) -> Vec<f64> {
+    let mut histogram = vec![0.0; num_bins];
+    if num_bins == 0 {
+        return histogram;
+    }
+
+    for (phase, magnitude) in phases.iter().zip(magnitudes) {
+        if *magnitude > threshold {
+            let position = (phase + PI).rem_euclid(2.0 * PI) / (2.0 * PI);
+            let bin = ((position * num_bins as f64) as usize).min(num_bins - 1);
+            histogram[bin] += magnitude;
+        }
+    }
+    histogram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stft::stft;
+
+    /// Deterministic uniform noise in `[-1, 1)` from a xorshift generator.
+    fn noise(seed: u64, len: usize) -> Vec<f64> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 11) as f64 / (1u64 << 52) as f64 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn analytic_signal_of_a_cosine_without_padding() {
+        // 1000 samples, so a power of two padding would smear the tone over many bins.
+        let phase = |n: usize| 2.0 * PI * 7.0 * n as f64 / 1000.0;
+        let cosine = (0..1000).map(|n| phase(n).cos()).collect::<Vec<_>>();
+        let analytic = analytic_signal(&cosine);
+        assert_eq!(analytic.len(), 1000);
+        for (n, value) in analytic.iter().enumerate() {
+            assert!((value - Complex::from_polar(1.0, phase(n))).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn phase_coherence_of_identical_and_independent_signals() {
+        let tone = (0..1024)
+            .map(|n| (2.0 * PI * 5.0 * n as f64 / 1024.0).sin())
+            .collect::<Vec<_>>();
+        assert!((phase_coherence(&tone, &tone) - 1.0).abs() < 1e-9);
+
+        let a = noise(1, 4096);
+        let b = noise(2, 4096);
+        assert!((phase_coherence(&a, &a) - 1.0).abs() < 1e-9);
+        assert!(phase_coherence(&a, &b) < 0.1);
+    }
+
+    #[test]
+    fn spectral_flux_spikes_at_a_frequency_change() {
+        const FRAME: usize = 64;
+        const HOP: usize = 32;
+        // 8 and 16 periods per frame, so every frame of a stationary part has the same
+        // magnitudes. The frequency jumps at sample 1024.
+        let signal = (0..2048)
+            .map(|n| {
+                let cycles = if n < 1024 { 8.0 } else { 16.0 };
+                (2.0 * PI * cycles * n as f64 / FRAME as f64).cos()
+            })
+            .collect::<Vec<_>>();
+        let frames = stft(&signal, FRAME, HOP)
+            .into_iter()
+            .map(|frame| frame.iter().map(|bin| bin.norm()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let flux = frames
+            .windows(2)
+            .map(|pair| spectral_flux(&pair[1], &pair[0]))
+            .collect::<Vec<_>>();
+
+        let spike = flux.iter().copied().fold(0.0, f64::max);
+        assert!(spike > 1.0);
+        // Frames fully before or after the change.
+        let change_frame = 1024 / HOP;
+        for (index, value) in flux.iter().enumerate() {
+            if index + 2 < change_frame || index > change_frame {
+                assert!(*value < 1e-9 * spike, "flux {value} at frame {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn spectral_flux_peaks_where_a_tone_starts() {
+        const FRAME: usize = 64;
+        const HOP: usize = 32;
+        const ONSET: usize = 1024;
+        let signal = (0..2048)
+            .map(|n| {
+                if n < ONSET {
+                    0.0
+                } else {
+                    (2.0 * PI * 8.0 * n as f64 / FRAME as f64).cos()
+                }
+            })
+            .collect::<Vec<_>>();
+        let frames = stft(&signal, FRAME, HOP)
+            .into_iter()
+            .map(|frame| frame.iter().map(|bin| bin.norm()).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        // `flux[i]` is the increase from frame `i` to frame `i + 1`.
+        let flux = frames
+            .windows(2)
+            .map(|pair| spectral_flux(&pair[1], &pair[0]))
+            .collect::<Vec<_>>();
+
+        let peak = (0..flux.len())
+            .max_by(|a, b| flux[*a].total_cmp(&flux[*b]))
+            .unwrap();
+        // The first frame that reaches past the onset.
+        let onset_frame = (ONSET - FRAME) / HOP + 1;
+        assert_eq!(peak + 1, onset_frame, "flux {flux:?}");
+        assert!(flux[..onset_frame - 1].iter().all(|value| *value == 0.0));
+    }
+}
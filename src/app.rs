@@ -1,39 +1,696 @@
-use core::{f64, fmt};
+use core::{cmp::Ordering, f64, fmt, hash};
+use std::{collections::HashMap, ops::RangeInclusive};
 
 use egui::{ComboBox, DragValue, TopBottomPanel};
 use egui_extras::{Column, TableBuilder};
-use egui_plot::{Line, PlotPoints};
+use egui_plot::{Line, PlotImage, PlotPoint, PlotPoints, Points, VLine};
 use num_complex::Complex;
 
-#[derive(Default)]
+use self::{
+    import::ImportedSignal, processing::SignalCache, recording::Recorder, report::generate_report,
+};
+use crate::{
+    analysis::{
+        am_fm_decompose, permutation_entropy, phase_coherence, pick_peaks, sample_entropy,
+        signal_bandwidth, snr_db, spectral_flux,
+    },
+    chirplet::chirplet_transform,
+    codegen::generate_egui_plot_code,
+    expression::Expression,
+    filter::FirType,
+    filter_bank::{qmf_filter_bank, qmf_synthesis},
+    gain_curve,
+    heatmap::heatmap_image,
+    keyboard::{piano_key_to_freq, render_mini_keyboard, PianoKey},
+    masking::compute_masking_threshold,
+    signal_flow::SignalFlow,
+    spectrum::{bin_to_frequency, fourier_coefficients, frequency_resolution, one_sided},
+    stft::stft,
+    weighting::FrequencyWeighting,
+    window::WindowFunction,
+};
+
+mod fir_design;
+mod gallery;
+mod import;
+mod phase_histogram;
+mod phase_portrait;
+mod power_budget;
+mod presets;
+mod processing;
+mod randomizer;
+mod recording;
+mod report;
+mod sensitivity;
+mod spectrogram;
+
+/// The format of saved sessions and presets. Version 1 has function frequencies in Hz instead of
+/// rad/s, version 2 custom formulas with the phase `p` and the time `t` instead of the phase `t`.
+const SETTINGS_VERSION: u32 = 2;
+
+#[derive(Default, serde::Deserialize, serde::Serialize)]
+#[serde(default = "TemplateApp::initial")]
 pub struct TemplateApp {
+    /// Missing in settings saved before versioning, which are version 0.
+    #[serde(default)]
+    settings_version: u32,
     functions: Vec<InputData>,
     num_samples: usize,
     input_signal_range: f64,
+    coherence_pair: (usize, usize),
+    show_signal_flow: bool,
+    clip_threshold: f64,
+    distortion_threshold_db: f64,
+    show_chirplet: bool,
+    chirplet_max_freq: f64,
+    chirplet_max_rate: f64,
+    chirplet_window_size: usize,
+    #[serde(skip)]
+    chirplet_frame: usize,
+    show_spectral_flux: bool,
+    stft_frame_size: usize,
+    stft_hop_size: usize,
+    onset_threshold: f64,
+    gain_curve: Vec<(f64, f64)>,
+    #[serde(skip)]
+    gain_curve_editing: bool,
+    #[serde(skip)]
+    dragged_gain_point: Option<usize>,
+    /// Keep only the bins inside `band`, in Hz, for the reconstruction.
+    band_filter_enabled: bool,
+    band: (f64, f64),
+    /// Dragging on the frequency plot selects the band instead of panning.
+    band_selecting: bool,
+    #[serde(skip)]
+    band_drag_start: Option<f64>,
+    phase_sync_target: PhaseSyncTarget,
+    bandwidth_power_percent: f64,
+    display_subsample_factor: usize,
+    show_am_fm: bool,
+    show_filter_bank: bool,
+    filter_bank_bands: usize,
+    #[serde(skip)]
+    show_report: bool,
+    #[serde(skip)]
+    report: String,
+    report_path: String,
+    show_phasors: bool,
+    phasor_count: usize,
+    #[serde(skip)]
+    phasor_time: f64,
+    phasor_speed: f64,
+    phasors_paused: bool,
+    keyboard_target: usize,
+    show_sensitivity: bool,
+    show_gallery: bool,
+    #[serde(skip)]
+    gallery_thumbnails: HashMap<PeriodicFunction, Vec<[f64; 2]>>,
+    frequency_weighting: FrequencyWeighting,
+    segment_count: usize,
+    segment_index: usize,
+    average_segments: bool,
+    show_folding: bool,
+    show_phase_portrait: bool,
+    phase_portrait_step: usize,
+    entropy_m: usize,
+    entropy_r: f64,
+    entropy_order: usize,
+    entropy_delay: usize,
+    show_recorder: bool,
+    #[serde(skip)]
+    recorder: Recorder,
+    show_spectrogram: bool,
+    track_peaks: bool,
+    max_peak_gap_bins: usize,
+    reassign_spectrogram: bool,
+    /// The window of the spectrogram, or `None` for the same one as the spectrum.
+    spectrogram_window: Option<WindowFunction>,
+    spectrogram_decibels: bool,
+    spectrogram_dynamic_range_db: f64,
+    summation_mode: SummationMode,
+    /// Applied to the combined wave before the FFT.
+    window: WindowFunction,
+    show_masking: bool,
+    masker_threshold_db: f64,
+    gate_enabled: bool,
+    soft_gate: bool,
+    gate_threshold_db: f64,
+    show_phase_histogram: bool,
+    phase_histogram_bins: usize,
+    phase_histogram_threshold: f64,
+    show_fir_design: bool,
+    fir_type: FirType,
+    fir_cutoffs: [f64; 2],
+    fir_order: usize,
+    fir_window: WindowFunction,
+    follow_dominant_peak: bool,
+    follow_window_hz: f64,
+    random_seed: u64,
+    random_frozen: bool,
+    spectrum_display: SpectrumDisplay,
+    show_phase_trace: bool,
+    #[serde(skip)]
+    imported: Option<ImportedSignal>,
+    #[serde(skip)]
+    import_error: Option<String>,
+    import_path: String,
+    csv_sample_rate: f64,
+    #[serde(skip)]
+    signal_cache: SignalCache,
+    show_presets: bool,
+    preset_path: String,
+    samples_path: String,
+    screenshot_path: String,
+    #[serde(skip)]
+    pasted_preset: String,
+    #[serde(skip)]
+    export_error: Option<String>,
+    /// Where the central panel's plots were drawn last frame, to crop screenshots to.
+    #[serde(skip)]
+    plots_rect: Option<egui::Rect>,
 }
 
 impl TemplateApp {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    /// Restores the previous session if there is one.
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        match cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Self>(storage, eframe::APP_KEY))
+        {
+            Some(app) => app.sanitized(),
+            None => Self::initial(),
+        }
+    }
+
+    /// Fixes up settings from storage or a preset that would break the plots.
+    fn sanitized(mut self) -> Self {
+        self.sanitize();
+        self
+    }
+
+    /// Like [`Self::sanitized`], in place.
+    fn sanitize(&mut self) {
+        if self.settings_version < 1 {
+            for function in &mut self.functions {
+                function.frequency = (function.frequency.0 / f64::consts::TAU).into();
+            }
+            self.chirplet_max_freq /= f64::consts::TAU;
+            self.chirplet_max_rate /= f64::consts::TAU;
+        }
+        if self.settings_version < 2 {
+            for function in &mut self.functions {
+                if let PeriodicFunction::Custom(expression) = &mut function.function {
+                    *expression = expression.renamed("t", "p");
+                }
+            }
+        }
+        self.settings_version = SETTINGS_VERSION;
+
+        // Rows with values like NaN would poison every plot, so they are dropped.
+        self.functions.retain(InputData::is_finite);
+        let initial = Self::initial();
+        if self.num_samples == 0 {
+            self.num_samples = initial.num_samples;
+        }
+        if !(self.input_signal_range.is_finite() && self.input_signal_range > 0.0) {
+            self.input_signal_range = initial.input_signal_range;
+        }
+
+        // The same limits as the controls, and the initial value for NaN.
+        let clamp = |value: f64, range: RangeInclusive<f64>, default: f64| {
+            if value.is_nan() {
+                default
+            } else {
+                value.clamp(*range.start(), *range.end())
+            }
+        };
+        self.clip_threshold = clamp(
+            self.clip_threshold,
+            0.001..=f64::MAX,
+            initial.clip_threshold,
+        );
+        self.distortion_threshold_db = clamp(
+            self.distortion_threshold_db,
+            f64::MIN..=f64::MAX,
+            initial.distortion_threshold_db,
+        );
+        self.chirplet_max_freq = clamp(
+            self.chirplet_max_freq,
+            0.1..=f64::MAX,
+            initial.chirplet_max_freq,
+        );
+        self.chirplet_max_rate = clamp(
+            self.chirplet_max_rate,
+            0.1..=f64::MAX,
+            initial.chirplet_max_rate,
+        );
+        self.onset_threshold = clamp(self.onset_threshold, 0.0..=1.0, initial.onset_threshold);
+        self.bandwidth_power_percent = clamp(
+            self.bandwidth_power_percent,
+            1.0..=100.0,
+            initial.bandwidth_power_percent,
+        );
+        self.phasor_speed = clamp(self.phasor_speed, 0.0..=10.0, initial.phasor_speed);
+        self.entropy_r = clamp(self.entropy_r, 0.0..=10.0, initial.entropy_r);
+        self.spectrogram_dynamic_range_db = clamp(
+            self.spectrogram_dynamic_range_db,
+            1.0..=200.0,
+            initial.spectrogram_dynamic_range_db,
+        );
+        self.masker_threshold_db = clamp(
+            self.masker_threshold_db,
+            -120.0..=0.0,
+            initial.masker_threshold_db,
+        );
+        self.gate_threshold_db = clamp(
+            self.gate_threshold_db,
+            -120.0..=0.0,
+            initial.gate_threshold_db,
+        );
+        self.phase_histogram_threshold = clamp(
+            self.phase_histogram_threshold,
+            0.0..=1.0,
+            initial.phase_histogram_threshold,
+        );
+        for (cutoff, default) in self.fir_cutoffs.iter_mut().zip(initial.fir_cutoffs) {
+            *cutoff = clamp(*cutoff, 0.0..=f64::MAX, default);
+        }
+        self.follow_window_hz = clamp(
+            self.follow_window_hz,
+            0.001..=f64::MAX,
+            initial.follow_window_hz,
+        );
+        self.csv_sample_rate = clamp(
+            self.csv_sample_rate,
+            0.001..=f64::MAX,
+            initial.csv_sample_rate,
+        );
+        if self.band.0.is_nan() || self.band.1.is_nan() {
+            self.band = initial.band;
+        }
+        self.band.0 = self.band.0.clamp(0.0, f64::MAX);
+        self.band.1 = self.band.1.clamp(self.band.0, f64::MAX);
+        self.gain_curve
+            .retain(|(x, gain)| x.is_finite() && !gain.is_nan());
+        for (_, gain) in &mut self.gain_curve {
+            *gain = gain.clamp(
+                *gain_curve::GAIN_RANGE_DB.start(),
+                *gain_curve::GAIN_RANGE_DB.end(),
+            );
+        }
+    }
+
+    /// The settings of a fresh start.
+    fn initial() -> Self {
         Self {
             num_samples: 1000,
-            input_signal_range: 3.14,
+            input_signal_range: f64::consts::PI,
+            coherence_pair: (0, 1),
+            clip_threshold: 1.0,
+            distortion_threshold_db: 40.0,
+            chirplet_max_freq: 20.0,
+            chirplet_max_rate: 20.0,
+            chirplet_window_size: 128,
+            stft_frame_size: 64,
+            stft_hop_size: 16,
+            band: (0.0, 2.0),
+            spectrogram_decibels: true,
+            spectrogram_dynamic_range_db: 80.0,
+            onset_threshold: 0.5,
+            bandwidth_power_percent: 90.0,
+            display_subsample_factor: 1,
+            filter_bank_bands: 4,
+            segment_count: 1,
+            phase_portrait_step: 1,
+            max_peak_gap_bins: 2,
+            masker_threshold_db: -40.0,
+            gate_threshold_db: -60.0,
+            phase_histogram_bins: 36,
+            phase_histogram_threshold: 0.01,
+            fir_cutoffs: [1.0, 5.0],
+            fir_order: 32,
+            fir_window: WindowFunction::Hamming,
+            follow_window_hz: 10.0,
+            entropy_m: 2,
+            entropy_r: 0.2,
+            entropy_order: 3,
+            entropy_delay: 1,
+            report_path: "report.txt".to_owned(),
+            import_path: "signal.wav".to_owned(),
+            preset_path: "preset.ron".to_owned(),
+            samples_path: "samples.csv".to_owned(),
+            screenshot_path: "plots.png".to_owned(),
+            csv_sample_rate: 1000.0,
+            phasor_count: 8,
+            phasor_speed: 0.2,
+            settings_version: SETTINGS_VERSION,
             ..Default::default()
         }
     }
 }
 
-#[derive(Clone)]
-struct InputData {
-    function: PeriodicFunction,
-    amplitude: f64,
-    frequency: f64,
-    y_shift: f64,
+/// An `f64` that can be hashed and totally ordered by comparing its bit pattern.
+#[derive(Clone, Copy, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct OrdFloat(pub f64);
+
+impl PartialEq for OrdFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for OrdFloat {}
+
+impl hash::Hash for OrdFloat {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+impl PartialOrd for OrdFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrdFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl From<f64> for OrdFloat {
+    fn from(value: f64) -> Self {
+        Self(value)
+    }
 }
 
-#[derive(Clone, PartialEq)]
-enum PeriodicFunction {
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct InputData {
+    pub function: PeriodicFunction,
+    pub amplitude: OrdFloat,
+    pub frequency: OrdFloat,
+    pub y_shift: OrdFloat,
+    pub phase: OrdFloat,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum PeriodicFunction {
     Sin,
     Cos,
+    Square,
+    Triangle,
+    Sawtooth,
+    /// High for the given fraction of every period and low for the rest.
+    Pulse(OrdFloat),
+    /// A formula of the time `t` and the phase `p = 2π * t * frequency + phase`.
+    Custom(Expression),
+}
+
+impl InputData {
+    /// A function of the given type with unit amplitude and frequency.
+    fn new(function: PeriodicFunction) -> Self {
+        Self {
+            function,
+            amplitude: 1.0.into(),
+            frequency: 1.0.into(),
+            y_shift: 0.0.into(),
+            phase: 0.0.into(),
+        }
+    }
+
+    /// A test tone without y shift and phase.
+    #[cfg(test)]
+    pub(crate) fn tone(function: PeriodicFunction, amplitude: f64, frequency: f64) -> Self {
+        Self {
+            amplitude: amplitude.into(),
+            frequency: frequency.into(),
+            ..Self::new(function)
+        }
+    }
+
+    fn is_finite(&self) -> bool {
+        [self.amplitude, self.frequency, self.y_shift, self.phase]
+            .iter()
+            .all(|value| value.0.is_finite())
+    }
+
+    /// The function's value at `x`, with the frequency in Hz.
+    pub fn value_at(&self, x: f64) -> f64 {
+        let phase = f64::consts::TAU * x * self.frequency.0 + self.phase.0;
+        self.function.apply(x, phase) * self.amplitude.0 + self.y_shift.0
+    }
+
+    /// The function at `count` evenly spaced points of `x_range`, both ends included, the way
+    /// the plot samples the [`PlotPoints`] of the function over its visible range.
+    pub fn sampled(&self, x_range: RangeInclusive<f64>, count: usize) -> Vec<[f64; 2]> {
+        let (start, end) = (*x_range.start(), *x_range.end());
+        let step = (end - start) / count.saturating_sub(1).max(1) as f64;
+        (0..count)
+            .map(|n| {
+                let x = start + step * n as f64;
+                [x, self.value_at(x)]
+            })
+            .collect()
+    }
+}
+
+impl From<&InputData> for PlotPoints {
+    fn from(function: &InputData) -> Self {
+        let function = function.clone();
+        PlotPoints::from_explicit_callback(
+            move |x| function.value_at(x),
+            f64::NEG_INFINITY..=f64::INFINITY,
+            10000,
+        )
+    }
+}
+
+/// The real parts of the complex samples, at their x positions.
+pub fn wave_plot_points(wave: &[(f64, Complex<f64>)]) -> PlotPoints {
+    wave.iter().map(|(x, y)| [*x, y.re]).collect()
+}
+
+/// Upper limit for the points drawn of a sampled wave, far more than fit on screen.
+const MAX_DISPLAY_POINTS: usize = 4000;
+
+/// Like [`wave_plot_points`], but only for the samples in `x_range` and with at most about
+/// `max_points` points. Long signals are split into buckets that are each drawn as their minimum
+/// and maximum, so the envelope survives even where there are many samples per pixel.
+pub fn decimated_plot_points(
+    wave: &[(f64, Complex<f64>)],
+    x_range: core::ops::RangeInclusive<f64>,
+    max_points: usize,
+) -> PlotPoints {
+    // Keep one sample on either side so the line reaches the edges of the range.
+    let start = wave
+        .partition_point(|(x, _)| x < x_range.start())
+        .saturating_sub(1);
+    let end = (wave.partition_point(|(x, _)| x <= x_range.end()) + 1).min(wave.len());
+    let visible = &wave[start..end.max(start)];
+    if visible.len() <= max_points {
+        return wave_plot_points(visible);
+    }
+
+    let bucket_size = visible.len().div_ceil(max_points.max(2) / 2);
+    let mut points = Vec::with_capacity(max_points + 1);
+    for bucket in visible.chunks(bucket_size) {
+        let (min, max) = bucket
+            .iter()
+            .fold((&bucket[0], &bucket[0]), |(min, max), sample| {
+                (
+                    if sample.1.re < min.1.re { sample } else { min },
+                    if sample.1.re > max.1.re { sample } else { max },
+                )
+            });
+        let (first, second) = if min.0 <= max.0 {
+            (min, max)
+        } else {
+            (max, min)
+        };
+        points.push([first.0, first.1.re]);
+        points.push([second.0, second.1.re]);
+    }
+    PlotPoints::from(points)
+}
+
+impl PeriodicFunction {
+    const ALL: [PeriodicFunction; 6] = [
+        PeriodicFunction::Sin,
+        PeriodicFunction::Cos,
+        PeriodicFunction::Square,
+        PeriodicFunction::Triangle,
+        PeriodicFunction::Sawtooth,
+        PeriodicFunction::Pulse(OrdFloat(0.25)),
+    ];
+
+    /// Value of the unscaled waveform at the given phase, with a period of 2π. Only custom
+    /// formulas also depend on the time.
+    fn apply(&self, time: f64, phase: f64) -> f64 {
+        match self {
+            PeriodicFunction::Sin => phase.sin(),
+            PeriodicFunction::Cos => phase.cos(),
+            PeriodicFunction::Square => square(phase),
+            PeriodicFunction::Triangle => triangle(phase),
+            PeriodicFunction::Sawtooth => sawtooth(phase),
+            PeriodicFunction::Pulse(duty_cycle) => pulse(phase, duty_cycle.0),
+            PeriodicFunction::Custom(expression) => expression.eval(time, phase),
+        }
+    }
+
+    /// Phase at which the function starts at its maximum.
+    fn peak_phase(&self) -> f64 {
+        match self {
+            PeriodicFunction::Sin | PeriodicFunction::Square | PeriodicFunction::Triangle => {
+                f64::consts::FRAC_PI_2
+            }
+            PeriodicFunction::Cos | PeriodicFunction::Custom(_) => 0.0,
+            PeriodicFunction::Sawtooth => f64::consts::PI,
+            PeriodicFunction::Pulse(duty_cycle) => f64::consts::PI * duty_cycle.0,
+        }
+    }
+
+    /// Whether the waveform jumps, so plots must not connect the samples on either side.
+    fn is_discontinuous(&self) -> bool {
+        matches!(
+            self,
+            PeriodicFunction::Square | PeriodicFunction::Sawtooth | PeriodicFunction::Pulse(_)
+        )
+    }
+}
+
+// The waveforms follow the sine: they rise through zero at phase 0 and peak around π/2.
+
+/// 1 for the first half of the period and -1 for the second.
+fn square(phase: f64) -> f64 {
+    if phase.rem_euclid(f64::consts::TAU) < f64::consts::PI {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+fn triangle(phase: f64) -> f64 {
+    f64::consts::FRAC_2_PI * phase.sin().asin()
+}
+
+/// 1 for the first `duty_cycle` of the period and -1 for the rest.
+fn pulse(phase: f64, duty_cycle: f64) -> f64 {
+    if (phase / f64::consts::TAU).rem_euclid(1.0) < duty_cycle {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Rises from -1 just after phase -π to 1 at π, where it jumps back.
+fn sawtooth(phase: f64) -> f64 {
+    1.0 - 2.0 * ((f64::consts::PI - phase) / f64::consts::TAU).rem_euclid(1.0)
+}
+
+/// Samples the function across `x_range` and splits the samples at its jumps.
+fn continuous_pieces(
+    function: &InputData,
+    x_range: core::ops::RangeInclusive<f64>,
+    num_samples: usize,
+) -> Vec<Vec<[f64; 2]>> {
+    let (start, end) = (*x_range.start(), *x_range.end());
+    let mut pieces: Vec<Vec<[f64; 2]>> = Vec::new();
+    let mut previous: Option<f64> = None;
+    for n in 0..num_samples {
+        let x = start + (end - start) * n as f64 / (num_samples - 1).max(1) as f64;
+        let y = function.value_at(x);
+        // Jumps are twice the amplitude, much more than a step within the waveform.
+        let jumped = previous.map_or(true, |previous| {
+            (y - previous).abs() > function.amplitude.0.abs()
+        });
+        if jumped {
+            pieces.push(Vec::new());
+        }
+        if let Some(piece) = pieces.last_mut() {
+            piece.push([x, y]);
+        }
+        previous = Some(y);
+    }
+    pieces
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum SummationMode {
+    #[default]
+    Coherent,
+    Incoherent,
+}
+
+/// What the Frequency Plot shows of each bin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum SpectrumDisplay {
+    #[default]
+    Magnitude,
+    Power,
+    Phase,
+}
+
+impl SpectrumDisplay {
+    const ALL: [SpectrumDisplay; 3] = [
+        SpectrumDisplay::Magnitude,
+        SpectrumDisplay::Power,
+        SpectrumDisplay::Phase,
+    ];
+
+    fn value(&self, bin: Complex<f64>) -> f64 {
+        match self {
+            SpectrumDisplay::Magnitude => bin.norm(),
+            SpectrumDisplay::Power => bin.norm_sqr(),
+            SpectrumDisplay::Phase => bin.arg(),
+        }
+    }
+}
+
+impl fmt::Display for SpectrumDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpectrumDisplay::Magnitude => write!(f, "Magnitude"),
+            SpectrumDisplay::Power => write!(f, "Power"),
+            SpectrumDisplay::Phase => write!(f, "Phase"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+enum PhaseSyncTarget {
+    #[default]
+    Zero,
+    Peak,
+    Trough,
+    Custom(f64),
+    PerFunction,
+}
+
+impl fmt::Display for PhaseSyncTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PhaseSyncTarget::Zero => write!(f, "Zero"),
+            PhaseSyncTarget::Peak => write!(f, "Peak"),
+            PhaseSyncTarget::Trough => write!(f, "Trough"),
+            PhaseSyncTarget::Custom(_) => write!(f, "Custom…"),
+            PhaseSyncTarget::PerFunction => write!(f, "Per function"),
+        }
+    }
+}
+
+fn sync_phases(functions: &mut [InputData], target: PhaseSyncTarget) {
+    for function in functions {
+        function.phase = match target {
+            PhaseSyncTarget::Zero => 0.0,
+            PhaseSyncTarget::Peak => f64::consts::FRAC_PI_2,
+            PhaseSyncTarget::Trough => -f64::consts::FRAC_PI_2,
+            PhaseSyncTarget::Custom(phase) => phase,
+            PhaseSyncTarget::PerFunction => function.function.peak_phase(),
+        }
+        .into();
+    }
 }
 
 impl fmt::Display for PeriodicFunction {
@@ -41,116 +698,1324 @@ impl fmt::Display for PeriodicFunction {
         match self {
             PeriodicFunction::Sin => write!(f, "Sin"),
             PeriodicFunction::Cos => write!(f, "Cos"),
+            PeriodicFunction::Square => write!(f, "Square"),
+            PeriodicFunction::Triangle => write!(f, "Triangle"),
+            PeriodicFunction::Sawtooth => write!(f, "Sawtooth"),
+            PeriodicFunction::Pulse(_) => write!(f, "Pulse"),
+            PeriodicFunction::Custom(_) => write!(f, "Custom"),
+        }
+    }
+}
+
+impl TemplateApp {
+    fn statistics_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        combined_wave: &[(f64, Complex<f64>)],
+        weighted_power: f64,
+        (bandwidth_lower, bandwidth_upper): (f64, f64),
+        reconstruction: Option<&[Complex<f64>]>,
+    ) {
+        let (coherent_peak, incoherent_peak) = self.peak_powers();
+        ui.label(format!(
+            "Peak power: coherent {coherent_peak:.3}, incoherent {incoherent_peak:.3} (ratio {:.2})",
+            coherent_peak / incoherent_peak
+        ));
+
+        let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let mean = samples.iter().sum::<f64>() / samples.len().max(1) as f64;
+        let std = (samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>()
+            / samples.len().max(1) as f64)
+            .sqrt();
+        // Sample entropy compares every pair of templates, so it is skipped for long signals.
+        let sample_entropy = (samples.len() <= 5000)
+            .then(|| sample_entropy(&samples, self.entropy_m, self.entropy_r * std));
+        let permutation_entropy =
+            permutation_entropy(&samples, self.entropy_order, self.entropy_delay);
+        ui.horizontal(|ui| {
+            match sample_entropy {
+                Some(entropy) => ui.label(format!("Sample entropy: {entropy:.3}")),
+                None => ui.label("Sample entropy: too many samples"),
+            };
+            ui.label("m");
+            ui.add(DragValue::new(&mut self.entropy_m).range(1..=10));
+            ui.label("r (× std)");
+            ui.add(
+                DragValue::new(&mut self.entropy_r)
+                    .speed(0.01)
+                    .range(0.0..=10.0),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Permutation entropy: {permutation_entropy:.3} bits"
+            ));
+            ui.label("Order");
+            ui.add(DragValue::new(&mut self.entropy_order).range(2..=7));
+            ui.label("Delay");
+            ui.add(DragValue::new(&mut self.entropy_delay).range(1..=100));
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{} weighted power", self.frequency_weighting));
+            ui.label(
+                egui::RichText::new(format!(
+                    "{:.1} {}",
+                    10.0 * weighted_power.log10(),
+                    self.frequency_weighting.unit()
+                ))
+                .strong(),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Bandwidth: {:.2} Hz ({bandwidth_lower:.1}–{bandwidth_upper:.1} Hz)",
+                bandwidth_upper - bandwidth_lower
+            ));
+            ui.add(
+                DragValue::new(&mut self.bandwidth_power_percent)
+                    .speed(0.5)
+                    .range(1.0..=100.0)
+                    .suffix(" % power"),
+            );
+        });
+
+        let snr = resynthesis_snr(combined_wave, reconstruction);
+
+        ui.horizontal(|ui| {
+            ui.label("Resynthesis SNR");
+            let text = match snr {
+                Some(snr) => format!("{snr:.1} dB"),
+                None => "n/a".to_owned(),
+            };
+            ui.label(egui::RichText::new(text).strong()).on_hover_text(
+                "Between the wave and the reconstruction from the filtered spectrum",
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Distortion threshold");
+            ui.add(
+                DragValue::new(&mut self.distortion_threshold_db)
+                    .speed(0.5)
+                    .suffix(" dB"),
+            );
+        });
+        if snr.is_some_and(|snr| snr < self.distortion_threshold_db) {
+            ui.colored_label(egui::Color32::YELLOW, "Audible distortion expected");
+        }
+
+        if self.functions.len() >= 2 {
+            let function_count = self.functions.len();
+            let (first, second) = &mut self.coherence_pair;
+            *first = (*first).min(function_count - 1);
+            *second = (*second).min(function_count - 1);
+
+            let samples = |function: &InputData| {
+                get_combined_wave(
+                    vec![function.clone()],
+                    self.num_samples,
+                    self.input_signal_range,
+                )
+                .iter()
+                .map(|(_, y)| y.re)
+                .collect::<Vec<_>>()
+            };
+            let coherence = phase_coherence(
+                &samples(&self.functions[*first]),
+                &samples(&self.functions[*second]),
+            );
+
+            ui.horizontal(|ui| {
+                ui.label("Phase coherence of");
+                ComboBox::from_id_salt("coherence_first")
+                    .selected_text(format!("#{}", *first + 1))
+                    .show_ui(ui, |ui| {
+                        for index in 0..function_count {
+                            ui.selectable_value(first, index, format!("#{}", index + 1));
+                        }
+                    });
+                ui.label("and");
+                ComboBox::from_id_salt("coherence_second")
+                    .selected_text(format!("#{}", *second + 1))
+                    .show_ui(ui, |ui| {
+                        for index in 0..function_count {
+                            ui.selectable_value(second, index, format!("#{}", index + 1));
+                        }
+                    });
+                ui.label(egui::RichText::new(format!("{coherence:.3}")).strong());
+            });
+        } else {
+            ui.label("Add at least two functions to see their phase coherence.");
+        }
+    }
+}
+
+/// The settings the chirplet transform depends on besides the signal.
+#[derive(Clone, Copy, PartialEq)]
+struct ChirpletKey {
+    max_freq: f64,
+    max_rate: f64,
+    window_size: usize,
+}
+
+impl TemplateApp {
+    fn chirplet_ui(&mut self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        const NUM_FREQS: usize = 48;
+        const NUM_RATES: usize = 33;
+
+        ui.horizontal(|ui| {
+            ui.label("Max frequency (Hz)");
+            ui.add(
+                DragValue::new(&mut self.chirplet_max_freq)
+                    .speed(0.1)
+                    .range(0.1..=f64::INFINITY),
+            );
+            ui.label("Max chirp rate (Hz/s)");
+            ui.add(
+                DragValue::new(&mut self.chirplet_max_rate)
+                    .speed(0.1)
+                    .range(0.1..=f64::INFINITY),
+            );
+            ui.label("Window size");
+            ui.add(
+                DragValue::new(&mut self.chirplet_window_size)
+                    .speed(1.0)
+                    .range(8..=4096),
+            );
+        });
+
+        let key = ChirpletKey {
+            max_freq: self.chirplet_max_freq,
+            max_rate: self.chirplet_max_rate,
+            window_size: self.chirplet_window_size,
+        };
+        let step_size = self.input_signal_range / self.num_samples.max(1) as f64;
+        let generation = self.signal_cache.generation();
+        let transform = self.signal_cache.chirplet.get((generation, key), || {
+            let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+            let center_freqs = (0..NUM_FREQS)
+                .map(|i| key.max_freq * i as f64 / (NUM_FREQS - 1) as f64)
+                .collect::<Vec<_>>();
+            let chirp_rates = (0..NUM_RATES)
+                .map(|i| key.max_rate * (2.0 * i as f64 / (NUM_RATES - 1) as f64 - 1.0))
+                .collect::<Vec<_>>();
+
+            // The transform works in radians per sample.
+            chirplet_transform(
+                &samples,
+                &center_freqs
+                    .iter()
+                    .map(|f| f64::consts::TAU * f * step_size)
+                    .collect::<Vec<_>>(),
+                &chirp_rates
+                    .iter()
+                    .map(|rate| f64::consts::TAU * rate * step_size * step_size)
+                    .collect::<Vec<_>>(),
+                key.window_size,
+            )
+            .into()
+        });
+
+        let num_frames = transform.first().map_or(0, Vec::len);
+        if num_frames == 0 {
+            ui.label("The signal is shorter than the chirplet window.");
+            return;
+        }
+        self.chirplet_frame = self.chirplet_frame.min(num_frames - 1);
+        ui.add(egui::Slider::new(&mut self.chirplet_frame, 0..=num_frames - 1).text("Frame"));
+
+        let frame = self.chirplet_frame;
+        let texture = self
+            .signal_cache
+            .chirplet_texture
+            .get((generation, key, frame), || {
+                // Rows are chirp rates and columns are center frequencies of the selected frame.
+                let slice = (0..NUM_RATES)
+                    .map(|rate| {
+                        (0..NUM_FREQS)
+                            .map(|freq| transform[freq * NUM_RATES + rate][frame].norm())
+                            .collect()
+                    })
+                    .collect::<Vec<_>>();
+                ui.ctx().load_texture(
+                    "chirplet",
+                    heatmap_image(&slice),
+                    egui::TextureOptions::NEAREST,
+                )
+            });
+
+        let max_freq = self.chirplet_max_freq;
+        let max_rate = self.chirplet_max_rate;
+        egui_plot::Plot::new("Chirplet Plot")
+            .x_axis_label("Center frequency (Hz)")
+            .y_axis_label("Chirp rate (Hz/s)")
+            .show(ui, |plot_ui| {
+                plot_ui.image(PlotImage::new(
+                    &texture,
+                    PlotPoint::new(max_freq / 2.0, 0.0),
+                    [max_freq as f32, 2.0 * max_rate as f32],
+                ));
+            });
+    }
+}
+
+impl TemplateApp {
+    fn spectral_flux_ui(&mut self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        ui.horizontal(|ui| {
+            ui.label("Frame size");
+            ui.add(
+                DragValue::new(&mut self.stft_frame_size)
+                    .speed(1.0)
+                    .range(2..=4096),
+            );
+            ui.label("Hop size");
+            ui.add(
+                DragValue::new(&mut self.stft_hop_size)
+                    .speed(1.0)
+                    .range(1..=4096),
+            );
+            ui.label("Onset threshold");
+            ui.add(
+                DragValue::new(&mut self.onset_threshold)
+                    .speed(0.01)
+                    .range(0.0..=1.0),
+            );
+        });
+
+        let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let magnitudes = stft(&samples, self.stft_frame_size, self.stft_hop_size)
+            .iter()
+            .map(|frame| {
+                frame[..frame.len() / 2]
+                    .iter()
+                    .map(|bin| bin.norm())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let flux = magnitudes
+            .windows(2)
+            .map(|frames| spectral_flux(&frames[1], &frames[0]))
+            .collect::<Vec<_>>();
+
+        let step_size = self.input_signal_range / self.num_samples.max(1) as f64;
+        let frame_time = |frame: usize| (frame * self.stft_hop_size) as f64 * step_size;
+        let max_flux = flux.iter().copied().fold(0.0, f64::max);
+        let onsets = pick_peaks(&flux, self.onset_threshold * max_flux);
+
+        egui_plot::Plot::new("Spectral Flux Plot")
+            .x_axis_label("Time")
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(
+                    flux.iter()
+                        .enumerate()
+                        .map(|(frame, flux)| [frame_time(frame + 1), *flux])
+                        .collect::<PlotPoints>(),
+                ));
+                for onset in onsets {
+                    plot_ui.vline(VLine::new(frame_time(onset + 1)).color(egui::Color32::RED));
+                }
+            });
+    }
+}
+
+impl TemplateApp {
+    /// Adds gain curve control points on click and moves them on drag.
+    fn edit_gain_curve(&mut self, plot_ui: &mut egui_plot::PlotUi) {
+        let response = plot_ui.response().clone();
+        let Some(mut pointer) = plot_ui.pointer_coordinate() else {
+            return;
+        };
+        pointer.y = pointer.y.clamp(
+            *gain_curve::GAIN_RANGE_DB.start(),
+            *gain_curve::GAIN_RANGE_DB.end(),
+        );
+        let pointer_screen = plot_ui.screen_from_plot(pointer);
+        let nearest_point = self
+            .gain_curve
+            .iter()
+            .enumerate()
+            .map(|(index, (x, gain))| {
+                let screen = plot_ui.screen_from_plot(PlotPoint::new(*x, *gain));
+                (index, screen.distance(pointer_screen))
+            })
+            .filter(|(_, distance)| *distance < 10.0)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index);
+
+        if response.drag_started() {
+            self.dragged_gain_point = nearest_point;
+        }
+        if let Some(index) = self.dragged_gain_point {
+            if response.dragged() {
+                self.gain_curve[index] = (pointer.x, pointer.y);
+            }
+        }
+        if response.drag_stopped() {
+            self.dragged_gain_point = None;
+        }
+        if response.clicked() && nearest_point.is_none() {
+            self.gain_curve.push((pointer.x, pointer.y));
+        }
+    }
+
+    /// Sets the band to the frequencies between where a drag started and the pointer.
+    fn select_band(&mut self, plot_ui: &mut egui_plot::PlotUi) {
+        let response = plot_ui.response().clone();
+        let Some(pointer) = plot_ui.pointer_coordinate() else {
+            return;
+        };
+        if response.drag_started() {
+            self.band_drag_start = Some(pointer.x);
+            self.band_filter_enabled = true;
+        }
+        if let Some(start) = self.band_drag_start {
+            if response.dragged() {
+                self.band = (start.min(pointer.x).max(0.0), start.max(pointer.x).max(0.0));
+            }
+        }
+        if response.drag_stopped() {
+            self.band_drag_start = None;
+        }
+    }
+}
+
+impl TemplateApp {
+    fn am_fm_ui(&self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let sample_rate = self.num_samples as f64 / self.input_signal_range;
+        let (am, fm) = am_fm_decompose(&samples, sample_rate);
+        let to_points = |values: &[f64]| {
+            combined_wave
+                .iter()
+                .zip(values.iter())
+                .map(|((x, _), y)| [*x, *y])
+                .collect::<PlotPoints>()
+        };
+
+        ui.label("Amplitude modulation (envelope)");
+        egui_plot::Plot::new("AM Plot")
+            .height(ui.available_height() / 2.0)
+            .link_axis("am_fm_axes", [true, false])
+            .show(ui, |plot_ui| plot_ui.line(Line::new(to_points(&am))));
+        ui.label("Frequency modulation (deviation in Hz)");
+        egui_plot::Plot::new("FM Plot")
+            .link_axis("am_fm_axes", [true, false])
+            .show(ui, |plot_ui| plot_ui.line(Line::new(to_points(&fm))));
+    }
+}
+
+impl TemplateApp {
+    fn filter_bank_ui(&mut self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        ComboBox::from_label("Number of bands")
+            .selected_text(format!("{}", self.filter_bank_bands))
+            .show_ui(ui, |ui| {
+                for bands in [2, 4, 8, 16] {
+                    ui.selectable_value(&mut self.filter_bank_bands, bands, format!("{bands}"));
+                }
+            });
+
+        let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let subbands = qmf_filter_bank(&samples, self.filter_bank_bands);
+        let reconstruction = qmf_synthesis(&subbands);
+        let squared_error = reconstruction
+            .iter()
+            .zip(samples.iter())
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>();
+        let rms_error = (squared_error / reconstruction.len().max(1) as f64).sqrt();
+        ui.label(format!("Reconstruction RMS error: {rms_error:.3e}"));
+
+        let step_size = self.input_signal_range / self.num_samples as f64;
+        let band_step = step_size * subbands.len() as f64;
+        let plot_height = ui.available_height() / subbands.len() as f32;
+        for (index, band) in subbands.iter().enumerate() {
+            egui_plot::Plot::new(("Filter Bank Plot", index))
+                .height(plot_height)
+                .link_axis("filter_bank_axes", [true, false])
+                .show(ui, |plot_ui| {
+                    plot_ui.line(
+                        Line::new(
+                            band.iter()
+                                .enumerate()
+                                .map(|(n, y)| [n as f64 * band_step, *y])
+                                .collect::<PlotPoints>(),
+                        )
+                        .name(format!("Band {}", index + 1)),
+                    );
+                });
+        }
+    }
+}
+
+impl TemplateApp {
+    fn report_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Copy to clipboard").clicked() {
+                ui.ctx().copy_text(self.report.clone());
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.text_edit_singleline(&mut self.report_path);
+                if ui.button("Save as .txt").clicked() {
+                    if let Err(err) = std::fs::write(&self.report_path, &self.report) {
+                        log::error!("Failed to save report to {}: {err}", self.report_path);
+                    }
+                }
+            }
+        });
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.report.as_str())
+                    .code_editor()
+                    .desired_width(f32::INFINITY),
+            );
+        });
+    }
+}
+
+impl TemplateApp {
+    /// `spectrum` has to be unwindowed for the epicycles to retrace the signal.
+    fn phasor_ui(&mut self, ui: &mut egui::Ui, spectrum: &[Complex<f64>]) {
+        ui.horizontal(|ui| {
+            ui.label("Phasors");
+            ui.add(
+                DragValue::new(&mut self.phasor_count)
+                    .speed(0.1)
+                    .range(1..=32),
+            );
+            ui.label("Speed");
+            ui.add(
+                DragValue::new(&mut self.phasor_speed)
+                    .speed(0.01)
+                    .range(0.0..=10.0),
+            );
+            let label = if self.phasors_paused { "Play" } else { "Pause" };
+            if ui.button(label).clicked() {
+                self.phasors_paused = !self.phasors_paused;
+            }
+        });
+
+        let period = self.input_signal_range;
+        if !self.phasors_paused {
+            self.phasor_time += ui.input(|i| i.stable_dt) as f64 * self.phasor_speed;
+            ui.ctx().request_repaint();
+        }
+        if period > 0.0 {
+            self.phasor_time %= period;
+        }
+
+        let terms = fourier_coefficients(spectrum, period, self.phasor_count);
+
+        let synthesize = |t: f64| {
+            terms
+                .iter()
+                .scan(Complex::ZERO, |tip, (frequency, coefficient)| {
+                    *tip +=
+                        coefficient * Complex::from_polar(1.0, f64::consts::TAU * frequency * t);
+                    Some(*tip)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let tips = synthesize(self.phasor_time);
+        let path_time = self.phasor_time;
+        let circle_color = ui.visuals().weak_text_color();
+        egui_plot::Plot::new("Phasor Plot")
+            .height(ui.available_height() / 2.0)
+            .data_aspect(1.0)
+            .show(ui, |plot_ui| {
+                let mut start = Complex::ZERO;
+                for (tip, (_, coefficient)) in tips.iter().zip(&terms) {
+                    let (center, radius) = (start, coefficient.norm());
+                    plot_ui.line(
+                        Line::new(PlotPoints::from_parametric_callback(
+                            move |angle| {
+                                (
+                                    center.re + radius * angle.cos(),
+                                    center.im + radius * angle.sin(),
+                                )
+                            },
+                            0.0..=f64::consts::TAU,
+                            64,
+                        ))
+                        .color(circle_color)
+                        .width(0.5),
+                    );
+                    plot_ui.line(Line::new(PlotPoints::from(vec![
+                        [start.re, start.im],
+                        [tip.re, tip.im],
+                    ])));
+                    start = *tip;
+                }
+                // The path of the last tip so far, its real part is the signal.
+                plot_ui.line(
+                    Line::new(PlotPoints::from_parametric_callback(
+                        |t| {
+                            let tip = synthesize(t).last().copied().unwrap_or_default();
+                            (tip.re, tip.im)
+                        },
+                        0.0..=path_time,
+                        500,
+                    ))
+                    .name("Tip path"),
+                );
+                plot_ui.points(
+                    Points::new(
+                        tips.iter()
+                            .map(|tip| [tip.re, tip.im])
+                            .collect::<PlotPoints>(),
+                    )
+                    .radius(2.0),
+                );
+            });
+
+        let steps = 500;
+        let traced = (0..=steps)
+            .map(|step| self.phasor_time * step as f64 / steps as f64)
+            .map(|t| [t, synthesize(t).last().map_or(0.0, |tip| tip.re)])
+            .collect::<PlotPoints>();
+        egui_plot::Plot::new("Phasor Synthesis Plot")
+            .include_x(0.0)
+            .include_x(period)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(traced).name("Running sum"))
+            });
+    }
+}
+
+impl TemplateApp {
+    fn keyboard_ui(&mut self, ui: &mut egui::Ui) {
+        if self.functions.is_empty() {
+            ui.label("Add a function to set its frequency from the keyboard.");
+            return;
+        }
+
+        self.keyboard_target = self.keyboard_target.min(self.functions.len() - 1);
+        ComboBox::from_label("Target function")
+            .selected_text(format!("#{}", self.keyboard_target + 1))
+            .show_ui(ui, |ui| {
+                for index in 0..self.functions.len() {
+                    ui.selectable_value(
+                        &mut self.keyboard_target,
+                        index,
+                        format!("#{}", index + 1),
+                    );
+                }
+            });
+
+        let frequency = &mut self.functions[self.keyboard_target].frequency;
+        let current_note =
+            PianoKey::all().find(|key| (piano_key_to_freq(*key) - frequency.0).abs() <= 2.0);
+        let mut selected_note = current_note;
+        render_mini_keyboard(ui, &mut selected_note);
+        if let Some(note) = selected_note.filter(|note| Some(*note) != current_note) {
+            *frequency = piano_key_to_freq(note).into();
+        }
+    }
+}
+
+impl TemplateApp {
+    /// Centers the x range on the largest bin below Nyquist, leaving out DC.
+    fn follow_peak(&self, plot_ui: &mut egui_plot::PlotUi, spectrum: &[Complex<f64>]) {
+        let Some(peak) = (1..spectrum.len() / 2)
+            .max_by(|a, b| spectrum[*a].norm().total_cmp(&spectrum[*b].norm()))
+        else {
+            return;
+        };
+        let center = peak as f64 / self.input_signal_range;
+        let half_width = self.follow_window_hz / 2.0;
+
+        let bounds = plot_ui.plot_bounds();
+        plot_ui.set_plot_bounds(egui_plot::PlotBounds::from_min_max(
+            [center - half_width, bounds.min()[1]],
+            [center + half_width, bounds.max()[1]],
+        ));
+    }
+
+    /// Draws the masking threshold of the spectral peaks and grays out the bins it masks.
+    fn plot_masking(&self, plot_ui: &mut egui_plot::PlotUi, spectrum: &[Complex<f64>]) {
+        let num_bins = spectrum.len() / 2;
+        let freq_resolution = frequency_resolution(self.input_signal_range);
+        let levels = spectrum[..num_bins]
+            .iter()
+            .map(|bin| 20.0 * bin.norm().log10())
+            .collect::<Vec<_>>();
+        let max_level = levels.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let maskers = pick_peaks(&levels, max_level + self.masker_threshold_db)
+            .into_iter()
+            .map(|k| (k as f64 * freq_resolution, levels[k]))
+            .collect::<Vec<_>>();
+        let threshold = compute_masking_threshold(&maskers, freq_resolution, num_bins);
+
+        let threshold_value = match self.spectrum_display {
+            SpectrumDisplay::Magnitude => Some(20.0),
+            SpectrumDisplay::Power => Some(10.0),
+            SpectrumDisplay::Phase => None,
+        };
+        if let Some(db_per_decade) = threshold_value {
+            plot_ui.line(
+                Line::new(
+                    threshold
+                        .iter()
+                        .enumerate()
+                        .map(|(k, db)| [k as f64 * freq_resolution, 10f64.powf(db / db_per_decade)])
+                        .collect::<PlotPoints>(),
+                )
+                .style(egui_plot::LineStyle::dashed_dense())
+                .name("Masking threshold"),
+            );
+        }
+        plot_ui.points(
+            Points::new(
+                (0..num_bins)
+                    .filter(|&k| levels[k] < threshold[k])
+                    .map(|k| {
+                        [
+                            k as f64 * freq_resolution,
+                            self.spectrum_display.value(spectrum[k]),
+                        ]
+                    })
+                    .collect::<PlotPoints>(),
+            )
+            .color(egui::Color32::GRAY)
+            .radius(2.0)
+            .name("Masked"),
+        );
+    }
+
+    /// Draws bins N/2..N at their negative frequencies and links each to its mirror bin.
+    fn plot_folding(&self, plot_ui: &mut egui_plot::PlotUi, spectrum: &[Complex<f64>]) {
+        let n = spectrum.len();
+        let freq_resolution = frequency_resolution(self.input_signal_range);
+        let value = |k: usize| self.spectrum_display.value(spectrum[k]);
+        let mirror_color = egui::Color32::from_rgba_unmultiplied(255, 140, 0, 120);
+
+        plot_ui.line(
+            Line::new(
+                (n / 2..n)
+                    .map(|k| [-((n - k) as f64) * freq_resolution, value(k)])
+                    .collect::<PlotPoints>(),
+            )
+            .color(mirror_color)
+            .name("Mirrored bins (negative frequencies)"),
+        );
+
+        let max = spectrum.iter().map(|bin| bin.norm()).fold(0.0, f64::max);
+        for (k, bin) in spectrum.iter().enumerate().take(n.div_ceil(2)).skip(1) {
+            if bin.norm() < 0.1 * max {
+                continue;
+            }
+            let frequency = k as f64 * freq_resolution;
+            plot_ui.line(
+                Line::new(PlotPoints::from(vec![
+                    [frequency, value(k)],
+                    [-frequency, value(n - k)],
+                ]))
+                .color(mirror_color)
+                .style(egui_plot::LineStyle::dotted_dense()),
+            );
         }
     }
 }
 
 impl eframe::App for TemplateApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        // An imported signal isn't saved, so neither is the sampling that matches it.
+        self.with_synthetic_sampling(|app| eframe::set_value(storage, eframe::APP_KEY, app));
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.update_recorder(ctx);
+        self.handle_dropped_files(ctx);
+        self.handle_screenshot(ctx);
+
+        let (combined_wave, spectrum, unwindowed_spectrum) = self.processed_signal();
+
+        let freq_resolution = frequency_resolution(self.input_signal_range);
+        let (bandwidth_lower, bandwidth_upper) = signal_bandwidth(
+            &spectrum[..spectrum.len() / 2]
+                .iter()
+                .map(|bin| bin.norm())
+                .collect::<Vec<_>>(),
+            freq_resolution,
+            self.bandwidth_power_percent / 100.0,
+        );
+
+        let weighted = self.weighted_spectrum(&spectrum, &unwindowed_spectrum);
+        let segment_length = combined_wave.len() / self.segment_count.max(1);
+        let segment_spectra = self.segment_spectra(&combined_wave);
+        self.segment_index = self.segment_index.min(self.segment_count.max(1) - 1);
+        let subsampled = self.subsampled(&combined_wave);
+
+        let filtered = self.filtered_signal(&unwindowed_spectrum);
+        let (filtered_spectrum, reconstruction) = match filtered {
+            Some(filtered) => (Some(filtered.spectrum), Some(filtered.reconstruction)),
+            None => (None, None),
+        };
+
+        let mut signal_flow = SignalFlow::default();
+        if self.show_signal_flow {
+            signal_flow = self.signal_cache.signal_flow();
+            let normalization = 2.0 / spectrum.len().max(1) as f64;
+            match &filtered_spectrum {
+                Some(filtered) => {
+                    signal_flow.record("Filter", filtered.iter().map(|y| y.norm() * normalization))
+                }
+                None => signal_flow.bypass("Filter"),
+            }
+            // Without a filter the inverse FFT gives back the summed wave.
+            match &reconstruction {
+                Some(reconstruction) => {
+                    signal_flow.record("IFFT", reconstruction.iter().map(|y| y.re))
+                }
+                None => signal_flow.record("IFFT", combined_wave.iter().map(|(_, y)| y.re)),
+            }
+        }
+
+        TopBottomPanel::top("menu_bar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    ui.checkbox(&mut self.show_presets, "Presets and export");
+                });
+                ui.menu_button("View", |ui| {
+                    ui.checkbox(&mut self.show_signal_flow, "Signal flow");
+                    ui.checkbox(&mut self.show_chirplet, "Chirplet transform");
+                    ui.checkbox(&mut self.show_spectrogram, "Spectrogram");
+                    ui.checkbox(&mut self.show_spectral_flux, "Spectral flux");
+                    ui.checkbox(&mut self.show_am_fm, "AM/FM decomposition");
+                    ui.checkbox(&mut self.show_filter_bank, "Filter bank");
+                    ui.checkbox(&mut self.show_fir_design, "FIR design");
+                    ui.checkbox(&mut self.show_phasors, "Phasor synthesis");
+                    ui.checkbox(&mut self.show_sensitivity, "Sensitivity");
+                    ui.checkbox(&mut self.show_phase_portrait, "Phase portrait");
+                    ui.checkbox(&mut self.show_phase_histogram, "Phase histogram");
+                    ui.checkbox(&mut self.show_recorder, "Recorder");
+                });
+            });
+        });
+
+        egui::Window::new("Signal flow")
+            .open(&mut self.show_signal_flow)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Clip threshold");
+                    ui.add(
+                        DragValue::new(&mut self.clip_threshold)
+                            .speed(0.1)
+                            .range(0.001..=f64::INFINITY),
+                    );
+                });
+                signal_flow.ui(ui, self.clip_threshold);
+            });
+
+        let mut show_chirplet = self.show_chirplet;
+        egui::Window::new("Chirplet transform")
+            .open(&mut show_chirplet)
+            .show(ctx, |ui| self.chirplet_ui(ui, &combined_wave));
+        self.show_chirplet = show_chirplet;
+
+        let mut show_spectrogram = self.show_spectrogram;
+        egui::Window::new("Spectrogram")
+            .open(&mut show_spectrogram)
+            .show(ctx, |ui| self.spectrogram_ui(ui, &combined_wave));
+        self.show_spectrogram = show_spectrogram;
+
+        let mut show_spectral_flux = self.show_spectral_flux;
+        egui::Window::new("Spectral flux")
+            .open(&mut show_spectral_flux)
+            .show(ctx, |ui| self.spectral_flux_ui(ui, &combined_wave));
+        self.show_spectral_flux = show_spectral_flux;
+
+        let mut show_am_fm = self.show_am_fm;
+        egui::Window::new("AM/FM Decomposition")
+            .open(&mut show_am_fm)
+            .show(ctx, |ui| self.am_fm_ui(ui, &combined_wave));
+        self.show_am_fm = show_am_fm;
+
+        let mut show_filter_bank = self.show_filter_bank;
+        egui::Window::new("Filter Bank")
+            .open(&mut show_filter_bank)
+            .show(ctx, |ui| self.filter_bank_ui(ui, &combined_wave));
+        self.show_filter_bank = show_filter_bank;
+
+        let mut show_fir_design = self.show_fir_design;
+        egui::Window::new("FIR Design")
+            .open(&mut show_fir_design)
+            .show(ctx, |ui| self.fir_design_ui(ui, &combined_wave));
+        self.show_fir_design = show_fir_design;
+
+        let mut show_report = self.show_report;
+        egui::Window::new("Report")
+            .open(&mut show_report)
+            .show(ctx, |ui| self.report_ui(ui));
+        self.show_report = show_report;
+
+        let mut show_phasors = self.show_phasors;
+        egui::Window::new("Phasor synthesis")
+            .open(&mut show_phasors)
+            .show(ctx, |ui| self.phasor_ui(ui, &unwindowed_spectrum));
+        self.show_phasors = show_phasors;
+
+        let mut show_sensitivity = self.show_sensitivity;
+        egui::Window::new("Sensitivity")
+            .open(&mut show_sensitivity)
+            .show(ctx, |ui| self.sensitivity_ui(ui));
+        self.show_sensitivity = show_sensitivity;
+
+        let mut show_gallery = self.show_gallery;
+        egui::Window::new("Waveform Gallery")
+            .open(&mut show_gallery)
+            .show(ctx, |ui| self.gallery_ui(ui));
+        self.show_gallery = show_gallery;
+
+        let mut show_phase_portrait = self.show_phase_portrait;
+        egui::Window::new("Phase Portrait")
+            .open(&mut show_phase_portrait)
+            .show(ctx, |ui| self.phase_portrait_ui(ui, &combined_wave));
+        self.show_phase_portrait = show_phase_portrait;
+
+        let mut show_phase_histogram = self.show_phase_histogram;
+        egui::Window::new("Phase histogram")
+            .open(&mut show_phase_histogram)
+            .show(ctx, |ui| self.phase_histogram_ui(ui, &spectrum));
+        self.show_phase_histogram = show_phase_histogram;
+
+        let mut show_recorder = self.show_recorder;
+        egui::Window::new("Recorder")
+            .open(&mut show_recorder)
+            .show(ctx, |ui| self.recorder_ui(ui));
+        self.show_recorder = show_recorder;
+
+        let mut show_presets = self.show_presets;
+        egui::Window::new("Presets and export")
+            .open(&mut show_presets)
+            .show(ctx, |ui| self.presets_ui(ui, &combined_wave));
+        // Loading a preset replaces the whole state, including whether this window is open.
+        self.show_presets &= show_presets;
+
         egui::SidePanel::left("side_panel")
             .min_width(ctx.available_rect().size().x * 0.2)
             .show(ctx, |ui| {
-                let wave_table = TableBuilder::new(ui)
-                    .striped(true)
-                    .column(Column::auto())
-                    .column(Column::exact(100.0))
-                    .column(Column::exact(100.0))
-                    .column(Column::exact(100.0));
-                wave_table
-                    .header(25.0, |mut header| {
-                        header.col(|ui| {
-                            ui.heading("Function");
-                        });
-                        header.col(|ui| {
-                            ui.heading("Amplitude");
-                        });
-                        header.col(|ui| {
-                            ui.heading("Frequency");
-                        });
-                        header.col(|ui| {
-                            ui.heading("Y Shift");
-                        });
-                    })
-                    .body(|body| {
-                        body.rows(25.0, self.functions.len(), |mut row| {
-                            let index = row.index();
-                            let function = &mut self.functions[index];
-
-                            row.col(|ui| {
-                                ComboBox::from_id_salt("function")
-                                    .selected_text(format!("{}", function.function))
-                                    .show_ui(ui, |ui| {
-                                        ui.selectable_value(
-                                            &mut function.function,
-                                            PeriodicFunction::Sin,
-                                            "Sin",
-                                        );
-                                        ui.selectable_value(
-                                            &mut function.function,
-                                            PeriodicFunction::Cos,
-                                            "Cos",
-                                        );
-                                    });
+                if self.imported.is_some() {
+                    ui.label("The functions are unused while an imported signal is shown.");
+                }
+                ui.add_enabled_ui(self.imported.is_none(), |ui| {
+                    let wave_table = TableBuilder::new(ui)
+                        .striped(true)
+                        .column(Column::auto())
+                        .column(Column::exact(100.0))
+                        .column(Column::exact(100.0))
+                        .column(Column::exact(100.0))
+                        .column(Column::exact(100.0));
+                    wave_table
+                        .header(25.0, |mut header| {
+                            header.col(|ui| {
+                                ui.heading("Function");
+                            });
+                            header.col(|ui| {
+                                ui.heading("Amplitude");
                             });
-                            row.col(|ui| {
-                                ui.add(DragValue::new(&mut function.amplitude).speed(0.1));
+                            header.col(|ui| {
+                                ui.heading("Frequency (Hz)");
                             });
-                            row.col(|ui| {
-                                ui.add(DragValue::new(&mut function.frequency).speed(0.1));
+                            header.col(|ui| {
+                                ui.heading("Y Shift");
                             });
-                            row.col(|ui| {
-                                ui.add(DragValue::new(&mut function.y_shift).speed(0.1));
+                            header.col(|ui| {
+                                ui.heading("Phase");
+                            });
+                        })
+                        .body(|body| {
+                            body.rows(25.0, self.functions.len(), |mut row| {
+                                let index = row.index();
+                                let function = &mut self.functions[index];
+
+                                row.col(|ui| {
+                                    ComboBox::from_id_salt("function")
+                                        .selected_text(format!("{}", function.function))
+                                        .show_ui(ui, |ui| {
+                                            // Compared by variant, so picking Pulse or Custom
+                                            // again keeps its duty cycle or formula.
+                                            let custom =
+                                                PeriodicFunction::Custom(Expression::new("sin(p)"));
+                                            for option in
+                                                PeriodicFunction::ALL.into_iter().chain([custom])
+                                            {
+                                                let selected = core::mem::discriminant(&option)
+                                                    == core::mem::discriminant(&function.function);
+                                                if ui
+                                                    .selectable_label(selected, format!("{option}"))
+                                                    .clicked()
+                                                    && !selected
+                                                {
+                                                    function.function = option;
+                                                }
+                                            }
+                                        });
+                                    if let PeriodicFunction::Pulse(duty_cycle) =
+                                        &mut function.function
+                                    {
+                                        ui.add(
+                                            DragValue::new(&mut duty_cycle.0)
+                                                .speed(0.01)
+                                                .range(0.0..=1.0),
+                                        )
+                                        .on_hover_text("Duty cycle");
+                                    }
+                                    if let PeriodicFunction::Custom(expression) =
+                                        &mut function.function
+                                    {
+                                        let mut source = expression.source().to_owned();
+                                        if ui
+                                            .add(
+                                                egui::TextEdit::singleline(&mut source)
+                                                    .desired_width(150.0)
+                                                    .hint_text("sin(p) * exp(-t)"),
+                                            )
+                                            .on_hover_text(
+                                                "t is the time in seconds and p the phase \
+                                                 2π·frequency·t + phase",
+                                            )
+                                            .changed()
+                                        {
+                                            *expression = Expression::new(source);
+                                        }
+                                        if let Some(error) = expression.error() {
+                                            ui.colored_label(ui.visuals().error_fg_color, "⚠")
+                                                .on_hover_text(error);
+                                        }
+                                    }
+                                });
+                                row.col(|ui| {
+                                    ui.add(DragValue::new(&mut function.amplitude.0).speed(0.1));
+                                });
+                                row.col(|ui| {
+                                    ui.add(DragValue::new(&mut function.frequency.0).speed(0.1));
+                                });
+                                row.col(|ui| {
+                                    ui.add(DragValue::new(&mut function.y_shift.0).speed(0.1));
+                                });
+                                row.col(|ui| {
+                                    ui.add(DragValue::new(&mut function.phase.0).speed(0.1));
+                                });
                             });
                         });
-                    });
 
-                if ui.button("Add function").clicked() {
-                    self.functions.push(InputData {
-                        function: PeriodicFunction::Sin,
-                        amplitude: 1.0,
-                        frequency: 1.0,
-                        y_shift: 0.0,
+                    ui.horizontal(|ui| {
+                        if ui.button("Add function").clicked() {
+                            self.functions.push(InputData::new(PeriodicFunction::Sin));
+                        }
+                        if ui.button("Waveform gallery").clicked() {
+                            self.show_gallery = true;
+                        }
+                        if ui.button("Copy as egui_plot callback").clicked() {
+                            ui.ctx().copy_text(generate_egui_plot_code(&self.functions));
+                        }
                     });
+                });
+
+                ui.collapsing("Keyboard", |ui| self.keyboard_ui(ui));
+                ui.collapsing("Random explorer", |ui| self.randomizer_ui(ui));
+
+                ui.horizontal(|ui| {
+                    ui.label("Sync phases to:");
+                    let target = &mut self.phase_sync_target;
+                    let custom_phase = match target {
+                        PhaseSyncTarget::Custom(phase) => *phase,
+                        _ => 0.0,
+                    };
+                    ComboBox::from_id_salt("phase_sync_target")
+                        .selected_text(format!("{target}"))
+                        .show_ui(ui, |ui| {
+                            for option in [
+                                PhaseSyncTarget::Zero,
+                                PhaseSyncTarget::Peak,
+                                PhaseSyncTarget::Trough,
+                                PhaseSyncTarget::Custom(custom_phase),
+                                PhaseSyncTarget::PerFunction,
+                            ] {
+                                ui.selectable_value(target, option, format!("{option}"));
+                            }
+                        });
+                    if let PhaseSyncTarget::Custom(phase) = target {
+                        ui.add(DragValue::new(phase).speed(0.1));
+                    }
+                    if ui.button("Apply").clicked() {
+                        sync_phases(&mut self.functions, self.phase_sync_target);
+                    }
+                });
+
+                ui.collapsing("Statistics", |ui| {
+                    self.statistics_ui(
+                        ui,
+                        &combined_wave,
+                        weighted.power,
+                        (bandwidth_lower, bandwidth_upper),
+                        reconstruction.as_deref(),
+                    )
+                });
+
+                ui.collapsing("Power budget", |ui| {
+                    self.power_budget_ui(ui, &combined_wave)
+                });
+
+                if ui.button("Generate report").clicked() {
+                    self.report = generate_report(
+                        self,
+                        &combined_wave,
+                        &spectrum,
+                        &unwindowed_spectrum,
+                        reconstruction.as_deref(),
+                    );
+                    self.show_report = true;
                 }
 
                 TopBottomPanel::bottom("bottom_controls").show_inside(ui, |ui| {
+                    self.import_ui(ui);
+
+                    // The imported file fixes both, so they are only shown.
+                    if self.imported.is_some() {
+                        ui.label(format!("Number of samples: {}", self.num_samples));
+                        ui.label(format!("Input signal range: {} s", self.input_signal_range));
+                    } else {
+                        ui.horizontal(|ui| {
+                            ui.label("Number of samples");
+                            ui.add(DragValue::new(&mut self.num_samples).speed(1.0));
+                        });
+
+                        ui.horizontal(|ui| {
+                            ui.label("Input signal range (s)");
+                            ui.add(
+                                DragValue::new(&mut self.input_signal_range)
+                                    .speed(0.1)
+                                    .range(0.0..=100.0),
+                            );
+                        });
+                    }
+
+                    ui.horizontal(|ui| {
+                        ui.label("Segments");
+                        ui.add(
+                            DragValue::new(&mut self.segment_count)
+                                .speed(0.1)
+                                .range(1..=16),
+                        );
+                        if self.segment_count > 1 {
+                            ui.add(
+                                egui::Slider::new(
+                                    &mut self.segment_index,
+                                    0..=self.segment_count - 1,
+                                )
+                                .text("Segment index"),
+                            );
+                            ui.checkbox(&mut self.average_segments, "Average (Bartlett)");
+                        }
+                    });
+
                     ui.horizontal(|ui| {
-                        ui.label("Number of samples");
-                        ui.add(DragValue::new(&mut self.num_samples).speed(1.0));
+                        ui.label("Summation mode:");
+                        ui.selectable_value(
+                            &mut self.summation_mode,
+                            SummationMode::Coherent,
+                            "Coherent",
+                        );
+                        ui.selectable_value(
+                            &mut self.summation_mode,
+                            SummationMode::Incoherent,
+                            "Incoherent",
+                        );
                     });
 
                     ui.horizontal(|ui| {
-                        ui.label("Input signal range");
+                        ui.label("Window:");
+                        ComboBox::from_id_salt("window")
+                            .selected_text(self.window.to_string())
+                            .show_ui(ui, |ui| {
+                                for window in WindowFunction::ALL {
+                                    ui.selectable_value(
+                                        &mut self.window,
+                                        window,
+                                        window.to_string(),
+                                    );
+                                }
+                            })
+                            .response
+                            .on_hover_text(
+                                "Applied before the FFT, with the magnitudes divided by the \
+                                 window's coherent gain so peaks keep their amplitude",
+                            );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.show_masking, "Show masking threshold");
+                        if self.show_masking {
+                            ui.label("Maskers above");
+                            ui.add(
+                                DragValue::new(&mut self.masker_threshold_db)
+                                    .speed(0.5)
+                                    .range(-120.0..=0.0)
+                                    .suffix(" dB"),
+                            );
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.follow_dominant_peak, "Follow dominant peak");
+                        ui.label("Zoom window");
+                        ui.add(
+                            DragValue::new(&mut self.follow_window_hz)
+                                .speed(0.1)
+                                .range(0.001..=f64::INFINITY)
+                                .suffix(" Hz"),
+                        );
+                    });
+
+                    ui.checkbox(&mut self.show_folding, "Show folding")
+                        .on_hover_text(
+                            "For a real signal bin N-k is the complex conjugate of bin k, so a \
+                             one-sided spectrum only needs N/2 bins and scales them by 2/N.",
+                        );
+
+                    ui.horizontal(|ui| {
+                        ComboBox::from_label("Frequency plot")
+                            .selected_text(format!("{}", self.spectrum_display))
+                            .show_ui(ui, |ui| {
+                                for display in SpectrumDisplay::ALL {
+                                    ui.selectable_value(
+                                        &mut self.spectrum_display,
+                                        display,
+                                        format!("{display}"),
+                                    );
+                                }
+                            });
+                        if self.spectrum_display != SpectrumDisplay::Phase {
+                            ui.checkbox(&mut self.show_phase_trace, "Phase trace");
+                        }
+                    });
+
+                    ComboBox::from_label("Frequency weighting")
+                        .selected_text(format!("{}", self.frequency_weighting))
+                        .show_ui(ui, |ui| {
+                            for weighting in FrequencyWeighting::ALL {
+                                ui.selectable_value(
+                                    &mut self.frequency_weighting,
+                                    weighting,
+                                    format!("{weighting}"),
+                                );
+                            }
+                        });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Display subsample factor");
                         ui.add(
-                            DragValue::new(&mut self.input_signal_range)
+                            DragValue::new(&mut self.display_subsample_factor)
                                 .speed(0.1)
-                                .range(0.0..=100.0),
+                                .range(1..=32),
+                        );
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.band_filter_enabled, "Band filter")
+                            .on_hover_text(
+                                "Zero the bins outside the band and show the inverse FFT as the \
+                                 reconstruction",
+                            );
+                        ui.add_enabled_ui(self.band_filter_enabled, |ui| {
+                            let max = frequency_resolution(self.input_signal_range)
+                                * (self.num_samples / 2) as f64;
+                            ui.label("Low");
+                            ui.add(
+                                DragValue::new(&mut self.band.0)
+                                    .speed(0.1)
+                                    .range(0.0..=self.band.1)
+                                    .suffix(" Hz"),
+                            );
+                            ui.label("High");
+                            ui.add(
+                                DragValue::new(&mut self.band.1)
+                                    .speed(0.1)
+                                    .range(self.band.0..=max.max(self.band.0))
+                                    .suffix(" Hz"),
+                            );
+                        });
+                        if ui
+                            .checkbox(&mut self.band_selecting, "Select band by dragging")
+                            .changed()
+                            && self.band_selecting
+                        {
+                            self.gain_curve_editing = false;
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        if ui
+                            .checkbox(&mut self.gain_curve_editing, "Edit gain curve")
+                            .changed()
+                            && self.gain_curve_editing
+                        {
+                            self.band_selecting = false;
+                        }
+                        if ui.button("Reset curve").clicked() {
+                            self.gain_curve.iter_mut().for_each(|(_, gain)| *gain = 0.0);
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.gate_enabled, "Enable gate")
+                            .on_hover_text(
+                                "Zero the bins below the threshold, relative to the largest bin, \
+                             and show the denoised signal as the reconstruction",
+                            );
+                        ui.add_enabled(
+                            self.gate_enabled,
+                            DragValue::new(&mut self.gate_threshold_db)
+                                .speed(0.5)
+                                .range(-120.0..=0.0)
+                                .suffix(" dB"),
                         );
+                        ui.add_enabled(
+                            self.gate_enabled,
+                            egui::Checkbox::new(&mut self.soft_gate, "Soft gate"),
+                        )
+                        .on_hover_text("Fade bins out as they approach the threshold");
                     });
                 });
             });
 
-        egui::CentralPanel::default().show(ctx, |ui| {
+        let plots = egui::CentralPanel::default().show(ctx, |ui| {
             egui_plot::Plot::new("Time Plot")
                 .height(ui.available_height() / 3.0)
                 .link_cursor("cursor_link", [true, true].into())
                 .link_axis("axes_group", [true, true])
                 .show(ui, |plot_ui| {
-                    let functions = self.functions.clone();
-
-                    for function in functions {
-                        let applied_function = match function.function {
-                            PeriodicFunction::Sin => f64::sin,
-                            PeriodicFunction::Cos => f64::cos,
-                        };
-                        plot_ui.line(Line::new(PlotPoints::from_explicit_callback(
-                            move |x| {
-                                applied_function(x * function.frequency) * function.amplitude
-                                    + function.y_shift
-                            },
-                            f64::NEG_INFINITY..=f64::INFINITY,
-                            10000,
-                        )));
+                    let bounds = plot_ui.plot_bounds();
+                    // The functions are unused while a file is imported, so show the file instead.
+                    if self.imported.is_some() {
+                        plot_ui.line(
+                            Line::new(decimated_plot_points(
+                                &combined_wave,
+                                bounds.min()[0]..=bounds.max()[0],
+                                MAX_DISPLAY_POINTS,
+                            ))
+                            .name("Imported"),
+                        );
+                        return;
+                    }
+                    for (index, function) in self.functions.iter().enumerate() {
+                        let color = function_color(index);
+                        if function.function.is_discontinuous() {
+                            for piece in continuous_pieces(
+                                function,
+                                bounds.min()[0]..=bounds.max()[0],
+                                10000,
+                            ) {
+                                plot_ui.line(Line::new(PlotPoints::from(piece)).color(color));
+                            }
+                        } else {
+                            plot_ui.line(Line::new(PlotPoints::from(function)).color(color));
+                        }
                     }
                 });
 
@@ -159,92 +2024,460 @@ impl eframe::App for TemplateApp {
                 .link_cursor("cursor_link", [true, true].into())
                 .link_axis("axes_group", [true, true])
                 .show(ui, |plot_ui| {
-                    let input = get_combined_wave(
-                        self.functions.clone(),
-                        self.num_samples,
-                        self.input_signal_range,
-                    );
+                    let bounds = plot_ui.plot_bounds();
+                    plot_ui.line(Line::new(decimated_plot_points(
+                        &combined_wave,
+                        bounds.min()[0]..=bounds.max()[0],
+                        MAX_DISPLAY_POINTS,
+                    )));
 
-                    plot_ui.line(Line::new(
-                        input
-                            .iter()
-                            .map(|(x, y)| [*x, y.re])
-                            .collect::<PlotPoints>(),
-                    ));
+                    if let Some(reconstruction) = &reconstruction {
+                        plot_ui.line(
+                            Line::new(
+                                combined_wave
+                                    .iter()
+                                    .zip(reconstruction.iter())
+                                    .map(|((x, _), y)| [*x, y.re])
+                                    .collect::<PlotPoints>(),
+                            )
+                            .name("Reconstruction"),
+                        );
+                    }
+
+                    if let Some((samples, _)) = subsampled.as_deref() {
+                        plot_ui.line(Line::new(wave_plot_points(samples)).name("Subsampled"));
+                    }
+
+                    if !segment_spectra.is_empty() {
+                        let start = self.segment_index * segment_length;
+                        plot_ui.line(
+                            Line::new(wave_plot_points(
+                                &combined_wave[start..start + segment_length],
+                            ))
+                            .width(3.0)
+                            .name(format!("Segment {}", self.segment_index + 1)),
+                        );
+                    }
                 });
 
+            // The gain curve is in dB, unlike the spectrum, so it gets its own plot below.
+            let show_gain_curve = self.gain_curve_editing || !self.gain_curve.is_empty();
             egui_plot::Plot::new("Frequency Plot")
-                .link_axis("axes_group", [true, true])
                 .clamp_grid(true)
-                .link_cursor("cursor_link", [true, true].into())
+                .x_axis_label("Frequency (Hz)")
+                .height(if show_gain_curve {
+                    ui.available_height() * 2.0 / 3.0
+                } else {
+                    ui.available_height()
+                })
+                .link_axis("frequency_axes", [true, false])
+                .allow_drag(!self.band_selecting)
                 .show(ui, |plot_ui| {
-                    let mut input = get_combined_wave(
-                        self.functions.clone(),
-                        self.num_samples,
-                        self.input_signal_range,
-                    );
+                    if self.follow_dominant_peak {
+                        self.follow_peak(plot_ui, &spectrum);
+                    }
+                    // A real signal's spectrum is symmetric, so only the bins up to Nyquist are
+                    // drawn.
+                    let display = self.spectrum_display;
+                    let num_bins = spectrum.len() / 2;
+                    let bin_points =
+                        |bins: &[Complex<f64>], value: &dyn Fn(Complex<f64>) -> f64| {
+                            one_sided(bins)
+                                .iter()
+                                .enumerate()
+                                .map(|(k, y)| {
+                                    [
+                                        bin_to_frequency(k, bins.len(), self.input_signal_range),
+                                        value(*y),
+                                    ]
+                                })
+                                .collect::<PlotPoints>()
+                        };
+                    let one_sided = |bins: &[Complex<f64>], scale: f64| {
+                        bin_points(bins, &|y| display.value(y * scale))
+                    };
+                    plot_ui.line(Line::new(one_sided(&spectrum, 1.0)).name(format!("{display}")));
+                    if self.show_phase_trace && display != SpectrumDisplay::Phase {
+                        plot_ui.line(
+                            Line::new(bin_points(&spectrum, &|y| y.arg())).name("Phase (rad)"),
+                        );
+                    }
 
-                    let mut new_input = input.iter().map(|(_, y)| *y).collect::<Vec<_>>();
-                    fft(&mut new_input);
+                    if self.show_folding {
+                        self.plot_folding(plot_ui, &spectrum);
+                    }
+                    if self.show_masking {
+                        self.plot_masking(plot_ui, &spectrum);
+                    }
 
-                    input = input
-                        .iter()
-                        .zip(new_input.iter())
-                        .map(|((x, _), y)| (*x, *y))
-                        .collect();
-
-                    plot_ui.line(Line::new(
-                        input
-                            .iter()
-                            .map(|(x, y)| [*x, y.re])
-                            .collect::<PlotPoints>(),
-                    ));
+                    if !segment_spectra.is_empty() {
+                        // Segment bin k sits at original bin k * segment_count, and the shorter
+                        // transform is scaled up to the same magnitudes.
+                        let bin_x = |k: usize| (k * self.segment_count) as f64 * freq_resolution;
+                        let scale = self.segment_count as f64;
+                        let segment_bins = segment_length / 2;
+
+                        if self.average_segments && display != SpectrumDisplay::Phase {
+                            let average_power = (0..segment_bins).map(|k| {
+                                segment_spectra
+                                    .iter()
+                                    .map(|spectrum| spectrum[k].norm_sqr())
+                                    .sum::<f64>()
+                                    / segment_spectra.len() as f64
+                            });
+                            plot_ui.line(
+                                Line::new(
+                                    average_power
+                                        .enumerate()
+                                        .map(|(k, power)| {
+                                            let magnitude = power.sqrt() * scale;
+                                            [bin_x(k), display.value(magnitude.into())]
+                                        })
+                                        .collect::<PlotPoints>(),
+                                )
+                                .name("Bartlett average"),
+                            );
+                        } else {
+                            for (index, segment_spectrum) in segment_spectra.iter().enumerate() {
+                                plot_ui.line(
+                                    Line::new(
+                                        segment_spectrum[..segment_bins]
+                                            .iter()
+                                            .enumerate()
+                                            .map(|(k, y)| [bin_x(k), display.value(y * scale)])
+                                            .collect::<PlotPoints>(),
+                                    )
+                                    .color(egui::ecolor::Hsva::new(
+                                        index as f32 / segment_spectra.len() as f32,
+                                        0.8,
+                                        0.9,
+                                        1.0,
+                                    ))
+                                    .name(format!("Segment {}", index + 1)),
+                                );
+                            }
+                        }
+                    }
+
+                    if let Some(weighted_spectrum) = &weighted.spectrum {
+                        plot_ui.line(
+                            Line::new(one_sided(weighted_spectrum, 1.0))
+                                .name(format!("{} weighted", self.frequency_weighting)),
+                        );
+                    }
+
+                    if let Some((_, subsampled_spectrum)) = subsampled.as_deref() {
+                        // The subsampled signal covers the same range, so bin k keeps its
+                        // frequency and only the magnitude scale changes.
+                        let factor = self.display_subsample_factor as f64;
+                        plot_ui.line(
+                            Line::new(one_sided(subsampled_spectrum, factor)).name("Subsampled"),
+                        );
+
+                        let nyquist_bin = spectrum.len() / (2 * self.display_subsample_factor);
+                        plot_ui.line(
+                            Line::new(
+                                (nyquist_bin + 1..num_bins)
+                                    .map(|k| {
+                                        [k as f64 * freq_resolution, display.value(spectrum[k])]
+                                    })
+                                    .collect::<PlotPoints>(),
+                            )
+                            .color(egui::Color32::RED)
+                            .name("Aliased"),
+                        );
+                    }
+
+                    for edge in [bandwidth_lower, bandwidth_upper] {
+                        plot_ui.vline(
+                            VLine::new(edge)
+                                .style(egui_plot::LineStyle::dashed_loose())
+                                .name("Bandwidth"),
+                        );
+                    }
+
+                    if self.band_selecting {
+                        self.select_band(plot_ui);
+                    }
+                    if self.band_filter_enabled {
+                        let (low, high) = self.band;
+                        for edge in [low, high] {
+                            plot_ui.vline(
+                                VLine::new(edge)
+                                    .color(egui::Color32::LIGHT_GREEN)
+                                    .name("Band"),
+                            );
+                        }
+                    }
                 });
+
+            if show_gain_curve {
+                egui_plot::Plot::new("Gain Curve")
+                    .clamp_grid(true)
+                    .x_axis_label("Frequency (Hz)")
+                    .y_axis_label("Gain (dB)")
+                    .include_y(-20.0)
+                    .include_y(20.0)
+                    .link_axis("frequency_axes", [true, false])
+                    .allow_drag(!self.gain_curve_editing)
+                    .show(ui, |plot_ui| {
+                        if self.gain_curve_editing {
+                            self.edit_gain_curve(plot_ui);
+                        }
+                        let bounds = plot_ui.plot_bounds();
+                        let gain_curve = self.gain_curve_spline();
+                        plot_ui.line(
+                            Line::new(PlotPoints::from_explicit_callback(
+                                move |x| gain_curve.gain_db(x),
+                                bounds.min()[0]..=bounds.max()[0],
+                                500,
+                            ))
+                            .name("Gain curve"),
+                        );
+                        plot_ui.points(
+                            Points::new(
+                                self.gain_curve
+                                    .iter()
+                                    .map(|(x, gain)| [*x, *gain])
+                                    .collect::<PlotPoints>(),
+                            )
+                            .radius(4.0),
+                        );
+                    });
+            }
         });
+        self.plots_rect = Some(plots.response.rect);
     }
 }
 
-fn get_combined_wave(
+/// Compares the wave with the inverse FFT of the filtered spectrum. Without a filter the two are
+/// the same up to rounding, so there is nothing to measure.
+fn resynthesis_snr(
+    combined_wave: &[(f64, Complex<f64>)],
+    reconstruction: Option<&[Complex<f64>]>,
+) -> Option<f64> {
+    reconstruction.map(|reconstruction| {
+        let original = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let reconstruction = reconstruction.iter().map(|y| y.re).collect::<Vec<_>>();
+        snr_db(&original, &reconstruction)
+    })
+}
+
+/// The Time Plot line color of the function at `index`, the same as egui_plot's automatic colors.
+fn function_color(index: usize) -> egui::Color32 {
+    let golden_ratio = (5f32.sqrt() - 1.0) / 2.0;
+    egui::ecolor::Hsva::new(index as f32 * golden_ratio, 0.85, 0.5, 1.0).into()
+}
+
+pub fn get_combined_wave(
     functions: Vec<InputData>,
     num_samples: usize,
     input_signal_range: f64,
+) -> Vec<(f64, Complex<f64>)> {
+    get_combined_wave_with_mode(
+        functions,
+        num_samples,
+        input_signal_range,
+        SummationMode::Coherent,
+    )
+}
+
+/// Like [`get_combined_wave`], but incoherent summation adds the powers of the functions instead
+/// of their amplitudes: `sqrt(Σ f_i(x)²)`.
+pub fn get_combined_wave_with_mode(
+    functions: Vec<InputData>,
+    num_samples: usize,
+    input_signal_range: f64,
+    mode: SummationMode,
 ) -> Vec<(f64, Complex<f64>)> {
     let step_size = input_signal_range / num_samples as f64;
-    let mut input = Vec::new();
+    let mut input = Vec::with_capacity(num_samples);
 
-    let mut i = 0.0;
-    while i < input_signal_range {
+    // Sample by index so rounding errors can't add an extra sample at the end of the range.
+    for n in 0..num_samples {
+        let i = n as f64 * step_size;
         let mut sum = 0.0;
         for function in functions.iter() {
-            let applied_function = match function.function {
-                PeriodicFunction::Sin => f64::sin,
-                PeriodicFunction::Cos => f64::cos,
+            let value = function.value_at(i);
+            sum += match mode {
+                SummationMode::Coherent => value,
+                SummationMode::Incoherent => value * value,
             };
-            sum += applied_function(i * function.frequency) * function.amplitude + function.y_shift;
+        }
+        if mode == SummationMode::Incoherent {
+            sum = sum.sqrt();
         }
         input.push((i, sum.into()));
-        i += step_size;
     }
 
     input
 }
 
-fn fft(input: &mut [Complex<f64>]) {
-    let n = input.len();
-    if n <= 1 {
-        return;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    use crate::{analysis::pick_peaks, fft::fft};
+
+    const NUM_SAMPLES: usize = 1024;
+
+    #[test]
+    fn settings_without_version_get_frequencies_in_hz() {
+        let app = TemplateApp {
+            functions: vec![InputData {
+                frequency: f64::consts::TAU.into(),
+                ..InputData::new(PeriodicFunction::Sin)
+            }],
+            ..TemplateApp::initial()
+        };
+        let text = ron::to_string(&app).unwrap();
+        let current = ron::from_str::<TemplateApp>(&text).unwrap().sanitized();
+        assert_eq!(current.functions[0].frequency.0, f64::consts::TAU);
+
+        let unversioned = text.replace(&format!("settings_version:{SETTINGS_VERSION},"), "");
+        assert_ne!(unversioned, text);
+        let migrated = ron::from_str::<TemplateApp>(&unversioned)
+            .unwrap()
+            .sanitized();
+        assert_eq!(migrated.settings_version, SETTINGS_VERSION);
+        assert!((migrated.functions[0].frequency.0 - 1.0).abs() < 1e-12);
     }
 
-    let mut even: Vec<Complex<f64>> = input.iter().step_by(2).copied().collect();
-    let mut odd: Vec<Complex<f64>> = input.iter().skip(1).step_by(2).copied().collect();
+    #[test]
+    fn three_tone_pipeline() {
+        // Over a range of 1 s the frequency in Hz equals the index of the FFT bin.
+        let functions = vec![
+            InputData::tone(PeriodicFunction::Sin, 1.0, 5.0),
+            InputData::tone(PeriodicFunction::Cos, 0.8, 12.0),
+            InputData::tone(PeriodicFunction::Sin, 0.5, 30.0),
+        ];
+        let samples = get_combined_wave(functions, NUM_SAMPLES, 1.0)
+            .into_iter()
+            .map(|(_, y)| y.re)
+            .collect::<Vec<_>>();
+        assert_eq!(samples.len(), NUM_SAMPLES);
+
+        let mut spectrum = samples
+            .iter()
+            .map(|x| Complex::from(*x))
+            .collect::<Vec<_>>();
+        fft(&mut spectrum);
+        assert!(spectrum
+            .iter()
+            .all(|bin| bin.re.is_finite() && bin.im.is_finite()));
 
-    fft(&mut even);
-    fft(&mut odd);
+        let normalization = 2.0 / NUM_SAMPLES as f64;
+        let magnitudes = spectrum[..NUM_SAMPLES / 2]
+            .iter()
+            .map(|bin| bin.norm() * normalization)
+            .collect::<Vec<_>>();
 
-    for i in 0..n / 2 {
-        let t =
-            Complex::from_polar(1.0, -2.0 * std::f64::consts::PI * i as f64 / n as f64) * odd[i];
-        input[i] = even[i] + t;
-        input[i + n / 2] = even[i] - t;
+        let peaks = pick_peaks(&magnitudes, 0.25);
+        assert_eq!(peaks.len(), 3, "peaks: {peaks:?}");
+        for (peak, expected) in peaks.iter().zip([5, 12, 30]) {
+            assert!(peak.abs_diff(expected) <= 1, "peak {peak} != {expected}");
+        }
+
+        let time_energy = samples.iter().map(|x| x * x).sum::<f64>();
+        let frequency_energy =
+            spectrum.iter().map(|bin| bin.norm_sqr()).sum::<f64>() / NUM_SAMPLES as f64;
+        assert!((time_energy - frequency_energy).abs() / time_energy < 1e-9);
+
+        assert!(magnitudes[0] < 1e-3, "DC magnitude {}", magnitudes[0]);
+    }
+
+    #[test]
+    fn input_data_plot_points_are_generated() {
+        let function = InputData::tone(PeriodicFunction::Sin, 2.0, 3.0);
+        // The callback is only sampled once the plot knows its visible range.
+        let points = PlotPoints::from(&function);
+        assert!(matches!(points, PlotPoints::Generator(_)));
+        assert!(points.points().is_empty());
+
+        // A quarter period of 3 Hz, from the zero crossing to the peak.
+        let points = PlotPoints::from(function.sampled(0.0..=1.0 / 12.0, 101));
+        let points = points.points();
+        assert_eq!(points.len(), 101);
+        assert_eq!((points[0].x, points[0].y), (0.0, 0.0));
+        assert_eq!(points[100].x, 1.0 / 12.0);
+        assert!((points[100].y - 2.0).abs() < 1e-12);
+        assert!((points[50].x - 1.0 / 24.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn wave_plot_points_match_samples() {
+        let wave = get_combined_wave(
+            vec![InputData::tone(PeriodicFunction::Cos, 1.0, 1.0)],
+            NUM_SAMPLES,
+            TAU,
+        );
+        let points = wave_plot_points(&wave);
+        assert_eq!(points.points().len(), NUM_SAMPLES);
+        for (point, (x, y)) in points.points().iter().zip(&wave) {
+            assert_eq!(point.x, *x);
+            assert_eq!(point.y, y.re);
+        }
+    }
+
+    #[test]
+    fn square_wave_has_only_odd_harmonics() {
+        let wave = get_combined_wave(
+            vec![InputData::tone(PeriodicFunction::Square, 1.0, 4.0)],
+            NUM_SAMPLES,
+            1.0,
+        );
+        let mut spectrum = wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+
+        // The fundamental of a unit square wave has amplitude 4/π, and harmonic h has 1/h of it.
+        let fundamental = spectrum[4].norm();
+        assert!(
+            (fundamental / (NUM_SAMPLES as f64 / 2.0) - 4.0 / std::f64::consts::PI).abs() < 0.01
+        );
+        for harmonic in [3.0, 5.0] {
+            let bin = spectrum[(4.0 * harmonic) as usize].norm();
+            assert!((bin / fundamental - 1.0 / harmonic).abs() < 0.01);
+        }
+        for even in [8, 16, 24] {
+            assert!(spectrum[even].norm() < 1e-6 * fundamental);
+        }
+    }
+
+    #[test]
+    fn long_waves_are_decimated_for_display() {
+        let wave = (0..100_000)
+            .map(|n| (n as f64, Complex::from(if n == 54_321 { 5.0 } else { 0.0 })))
+            .collect::<Vec<_>>();
+        let points = decimated_plot_points(&wave, 0.0..=100_000.0, 1000)
+            .points()
+            .to_vec();
+        assert!(points.len() <= 1001);
+        assert!(points.windows(2).all(|pair| pair[0].x <= pair[1].x));
+        // A single spike still shows up.
+        assert!(points.iter().any(|point| point.y == 5.0));
+
+        let zoomed = decimated_plot_points(&wave, 10.0..=20.0, 1000)
+            .points()
+            .to_vec();
+        assert_eq!(zoomed.first().unwrap().x, 9.0);
+        assert_eq!(zoomed.len(), 13);
+    }
+
+    #[test]
+    fn custom_formulas_of_the_phase_use_p() {
+        let app = TemplateApp {
+            settings_version: 1,
+            functions: vec![InputData::new(PeriodicFunction::Custom(Expression::new(
+                "sin(t) + t",
+            )))],
+            ..TemplateApp::initial()
+        }
+        .sanitized();
+        assert_eq!(
+            app.functions[0].function,
+            PeriodicFunction::Custom(Expression::new("sin(p) + p"))
+        );
+        assert_eq!(app.functions[0].frequency.0, 1.0);
     }
 }
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use egui::{ComboBox, DragValue};
+use egui_extras::{Column, TableBuilder};
+use egui_plot::{Line, PlotPoints};
+use num_complex::Complex;
+
+use super::{wave_plot_points, TemplateApp};
+use crate::{
+    filter::{
+        design_fir_window, filter_to_difference_equation, fir_response, overlap_add, FirType,
+    },
+    window::WindowFunction,
+};
+
+const RESPONSE_POINTS: usize = 512;
+const PLOT_HEIGHT: f32 = 150.0;
+
+/// The settings the FIR filter is designed from.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) struct FirKey {
+    fir_type: FirType,
+    cutoffs: [f64; 2],
+    order: usize,
+    window: WindowFunction,
+    sample_rate: f64,
+}
+
+/// The taps of the designed filter and its frequency response up to Nyquist.
+pub(super) struct FirDesign {
+    taps: Vec<f64>,
+    response: Vec<(f64, Complex<f64>)>,
+}
+
+impl TemplateApp {
+    pub(super) fn fir_design_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        combined_wave: &[(f64, Complex<f64>)],
+    ) {
+        let sample_rate = self.num_samples as f64 / self.input_signal_range;
+        let nyquist = sample_rate / 2.0;
+
+        ui.horizontal(|ui| {
+            ComboBox::from_label("Type")
+                .selected_text(format!("{}", self.fir_type))
+                .show_ui(ui, |ui| {
+                    for filter_type in FirType::ALL {
+                        ui.selectable_value(
+                            &mut self.fir_type,
+                            filter_type,
+                            format!("{filter_type}"),
+                        );
+                    }
+                });
+            ComboBox::from_label("Window")
+                .selected_text(format!("{}", self.fir_window))
+                .show_ui(ui, |ui| {
+                    for window in WindowFunction::ALL {
+                        ui.selectable_value(&mut self.fir_window, window, format!("{window}"));
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Cutoff (Hz)");
+            for cutoff in &mut self.fir_cutoffs[..self.fir_type.num_cutoffs()] {
+                ui.add(DragValue::new(cutoff).speed(0.1).range(0.0..=nyquist));
+            }
+            ui.label("Order");
+            ui.add(DragValue::new(&mut self.fir_order).range(1..=512));
+        });
+
+        let key = FirKey {
+            fir_type: self.fir_type,
+            cutoffs: self.fir_cutoffs,
+            order: self.fir_order,
+            window: self.fir_window,
+            sample_rate,
+        };
+        let design = self.signal_cache.fir_design.get(key, || {
+            let taps = design_fir_window(
+                key.fir_type,
+                &key.cutoffs,
+                key.order,
+                key.window,
+                key.sample_rate,
+            );
+            let response = (0..=RESPONSE_POINTS)
+                .map(|i| {
+                    let frequency = nyquist * i as f64 / RESPONSE_POINTS as f64;
+                    (frequency, fir_response(&taps, frequency, sample_rate))
+                })
+                .collect();
+            Arc::new(FirDesign { taps, response })
+        });
+        let (taps, response) = (&design.taps, &design.response);
+
+        ui.label("Magnitude (dB)");
+        egui_plot::Plot::new("FIR Magnitude Plot")
+            .height(PLOT_HEIGHT)
+            .link_axis("fir_bode_axes", [true, false])
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(
+                    response
+                        .iter()
+                        .map(|(f, h)| [*f, 20.0 * h.norm().max(1e-12).log10()])
+                        .collect::<PlotPoints>(),
+                ))
+            });
+        ui.label("Phase (rad)");
+        egui_plot::Plot::new("FIR Phase Plot")
+            .height(PLOT_HEIGHT)
+            .link_axis("fir_bode_axes", [true, false])
+            .x_axis_label("Frequency (Hz)")
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(
+                    response
+                        .iter()
+                        .map(|(f, h)| [*f, h.arg()])
+                        .collect::<PlotPoints>(),
+                ))
+            });
+
+        let generation = self.signal_cache.generation();
+        let filtered = self.signal_cache.fir_filtered.get((generation, key), || {
+            let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+            overlap_add(&samples, taps).into()
+        });
+        ui.label("Filtered signal");
+        egui_plot::Plot::new("FIR Filtered Plot")
+            .height(PLOT_HEIGHT)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(wave_plot_points(combined_wave)).name("Original"));
+                plot_ui.line(
+                    Line::new(
+                        combined_wave
+                            .iter()
+                            .zip(filtered.iter())
+                            .map(|((x, _), y)| [*x, *y])
+                            .collect::<PlotPoints>(),
+                    )
+                    .name("Filtered"),
+                );
+            });
+
+        ui.collapsing("Coefficients", |ui| {
+            ui.label(filter_to_difference_equation(taps, &[1.0]));
+            TableBuilder::new(ui)
+                .striped(true)
+                .column(Column::auto())
+                .column(Column::remainder())
+                .max_scroll_height(200.0)
+                .header(20.0, |mut header| {
+                    header.col(|ui| {
+                        ui.strong("n");
+                    });
+                    header.col(|ui| {
+                        ui.strong("h[n]");
+                    });
+                })
+                .body(|body| {
+                    body.rows(18.0, taps.len(), |mut row| {
+                        let n = row.index();
+                        row.col(|ui| {
+                            ui.label(format!("{n}"));
+                        });
+                        row.col(|ui| {
+                            ui.label(format!("{:.6}", taps[n]));
+                        });
+                    });
+                });
+        });
+    }
+}
@@ -0,0 +1,53 @@
+use egui_plot::{Line, PlotPoints};
+
+use super::{InputData, PeriodicFunction, TemplateApp};
+use crate::expression::Expression;
+
+const THUMBNAIL_SAMPLES: usize = 50;
+/// The formula the gallery shows for custom functions, a tone that decays over time.
+const CUSTOM_EXAMPLE: &str = "sin(p) * exp(-2*t)";
+
+impl TemplateApp {
+    pub(super) fn gallery_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            let custom = PeriodicFunction::Custom(Expression::new(CUSTOM_EXAMPLE));
+            for function in PeriodicFunction::ALL.into_iter().chain([custom]) {
+                let thumbnail = self
+                    .gallery_thumbnails
+                    .entry(function.clone())
+                    .or_insert_with(|| thumbnail(&InputData::new(function.clone())));
+
+                let clicked = ui
+                    .vertical(|ui| {
+                        let response = egui_plot::Plot::new(("Gallery Plot", function.to_string()))
+                            .width(100.0)
+                            .height(60.0)
+                            .show_axes(false)
+                            .show_grid(false)
+                            .show_x(false)
+                            .show_y(false)
+                            .allow_drag(false)
+                            .allow_zoom(false)
+                            .allow_scroll(false)
+                            .allow_boxed_zoom(false)
+                            .show(ui, |plot_ui| {
+                                plot_ui.line(Line::new(PlotPoints::from(thumbnail.clone())))
+                            })
+                            .response;
+                        ui.label(function.to_string());
+                        response.clicked()
+                    })
+                    .inner;
+
+                if clicked {
+                    self.functions.push(InputData::new(function));
+                }
+            }
+        });
+    }
+}
+
+/// Samples one period of the function.
+fn thumbnail(function: &InputData) -> Vec<[f64; 2]> {
+    function.sampled(0.0..=1.0 / function.frequency.0, THUMBNAIL_SAMPLES)
+}
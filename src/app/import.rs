@@ -0,0 +1,166 @@
+use egui::DragValue;
+use num_complex::Complex;
+
+use super::TemplateApp;
+use crate::signal_file::{parse_csv, parse_wav};
+
+/// A recorded signal that replaces the synthesized sum of functions.
+pub(super) struct ImportedSignal {
+    name: String,
+    samples: Vec<f64>,
+    sample_rate: f64,
+    /// CSV files don't store a sample rate, so the user may change it.
+    editable_rate: bool,
+    /// The synthetic `num_samples` and `input_signal_range` to go back to.
+    previous_sampling: (usize, f64),
+}
+
+impl ImportedSignal {
+    /// The samples in the same form as [`super::get_combined_wave`] returns them.
+    pub(super) fn wave(&self) -> Vec<(f64, Complex<f64>)> {
+        self.samples
+            .iter()
+            .enumerate()
+            .map(|(n, y)| (n as f64 / self.sample_rate, Complex::from(*y)))
+            .collect()
+    }
+}
+
+impl TemplateApp {
+    /// Loads a WAV or CSV file dropped onto the window.
+    pub(super) fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let Some(file) = ctx.input(|input| input.raw.dropped_files.first().cloned()) else {
+            return;
+        };
+        let bytes = match (&file.bytes, &file.path) {
+            (Some(bytes), _) => Ok(bytes.to_vec()),
+            (None, Some(path)) => std::fs::read(path).map_err(|err| err.to_string()),
+            (None, None) => Err("The dropped file could not be read".to_owned()),
+        };
+        let name = match &file.path {
+            Some(path) => path.display().to_string(),
+            None => file.name.clone(),
+        };
+        match bytes {
+            Ok(bytes) => self.import_signal(name, &bytes),
+            Err(err) => self.import_error = Some(err),
+        }
+    }
+
+    /// Replaces the synthesized wave with the file's samples. Files ending in `.wav` are read as
+    /// WAV, everything else as CSV.
+    fn import_signal(&mut self, name: String, bytes: &[u8]) {
+        let parsed = if name.to_lowercase().ends_with(".wav") {
+            parse_wav(bytes).map(|(samples, rate)| (samples, rate, false))
+        } else {
+            std::str::from_utf8(bytes)
+                .map_err(|_| "The file is not valid UTF-8 text".to_owned())
+                .and_then(parse_csv)
+                .map(|samples| (samples, self.csv_sample_rate, true))
+        };
+
+        match parsed {
+            Ok((samples, sample_rate, editable_rate)) => {
+                let previous_sampling = match self.imported.take() {
+                    Some(imported) => imported.previous_sampling,
+                    None => (self.num_samples, self.input_signal_range),
+                };
+                self.num_samples = samples.len();
+                self.input_signal_range = samples.len() as f64 / sample_rate;
+                self.imported = Some(ImportedSignal {
+                    name,
+                    samples,
+                    sample_rate,
+                    editable_rate,
+                    previous_sampling,
+                });
+                self.import_error = None;
+                self.signal_cache.invalidate();
+            }
+            Err(err) => self.import_error = Some(err),
+        }
+    }
+
+    /// Goes back to the synthesized wave and its sampling settings.
+    fn clear_import(&mut self) {
+        if let Some(imported) = self.imported.take() {
+            (self.num_samples, self.input_signal_range) = imported.previous_sampling;
+        }
+        self.signal_cache.invalidate();
+    }
+
+    /// The sampling settings to persist, which are the synthetic ones while a file is imported.
+    pub(super) fn synthetic_sampling(&self) -> (usize, f64) {
+        match &self.imported {
+            Some(imported) => imported.previous_sampling,
+            None => (self.num_samples, self.input_signal_range),
+        }
+    }
+
+    /// Runs `f` with the synthetic sampling settings in place of the imported file's.
+    pub(super) fn with_synthetic_sampling<R>(&mut self, f: impl FnOnce(&Self) -> R) -> R {
+        let sampling = (self.num_samples, self.input_signal_range);
+        (self.num_samples, self.input_signal_range) = self.synthetic_sampling();
+        let result = f(self);
+        (self.num_samples, self.input_signal_range) = sampling;
+        result
+    }
+
+    pub(super) fn import_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.text_edit_singleline(&mut self.import_path)
+                    .on_hover_text("Path of a WAV file or a single-column CSV file");
+                if ui.button("Import signal").clicked() {
+                    match std::fs::read(&self.import_path) {
+                        Ok(bytes) => self.import_signal(self.import_path.clone(), &bytes),
+                        Err(err) => self.import_error = Some(err.to_string()),
+                    }
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            ui.label("Drop a WAV or a single-column CSV file to import it");
+
+            if self.imported.is_some() && ui.button("Clear import").clicked() {
+                self.clear_import();
+            }
+        });
+
+        if let Some(imported) = &mut self.imported {
+            ui.horizontal(|ui| {
+                ui.label(format!(
+                    "Imported {} ({} samples)",
+                    imported.name,
+                    imported.samples.len()
+                ));
+                ui.label("Sample rate");
+                let response = ui.add_enabled(
+                    imported.editable_rate,
+                    DragValue::new(&mut imported.sample_rate)
+                        .speed(1.0)
+                        .range(1e-3..=f64::INFINITY)
+                        .suffix(" Hz"),
+                );
+                if response.changed() {
+                    self.csv_sample_rate = imported.sample_rate;
+                    self.input_signal_range = imported.samples.len() as f64 / imported.sample_rate;
+                }
+            });
+        } else {
+            ui.horizontal(|ui| {
+                ui.label("CSV sample rate");
+                ui.add(
+                    DragValue::new(&mut self.csv_sample_rate)
+                        .speed(1.0)
+                        .range(1e-3..=f64::INFINITY)
+                        .suffix(" Hz"),
+                );
+            });
+        }
+
+        if let Some(error) = &self.import_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+}
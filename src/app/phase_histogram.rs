@@ -0,0 +1,67 @@
+use egui::{DragValue, Shape, Stroke};
+use num_complex::Complex;
+
+use super::TemplateApp;
+use crate::analysis::polar_histogram;
+
+const ROSE_RADIUS: f32 = 120.0;
+
+impl TemplateApp {
+    /// Rose chart of the phases of the FFT bins up to Nyquist, weighted by their magnitudes.
+    pub(super) fn phase_histogram_ui(&mut self, ui: &mut egui::Ui, spectrum: &[Complex<f64>]) {
+        ui.horizontal(|ui| {
+            ui.label("Bins");
+            ui.add(DragValue::new(&mut self.phase_histogram_bins).range(1..=360));
+            ui.label("Threshold");
+            ui.add(
+                DragValue::new(&mut self.phase_histogram_threshold)
+                    .speed(0.001)
+                    .range(0.0..=1.0),
+            )
+            .on_hover_text("Ignore bins below this fraction of the largest magnitude");
+        });
+
+        let half = &spectrum[..spectrum.len() / 2];
+        let phases = half.iter().map(|bin| bin.arg()).collect::<Vec<_>>();
+        let magnitudes = half.iter().map(|bin| bin.norm()).collect::<Vec<_>>();
+        let max_magnitude = magnitudes.iter().copied().fold(0.0, f64::max);
+        let histogram = polar_histogram(
+            &phases,
+            self.phase_histogram_bins,
+            &magnitudes,
+            self.phase_histogram_threshold * max_magnitude,
+        );
+        let max_weight = histogram.iter().copied().fold(0.0, f64::max);
+
+        let (rect, _) =
+            ui.allocate_exact_size(egui::Vec2::splat(2.0 * ROSE_RADIUS), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let center = rect.center();
+        let stroke = Stroke::new(1.0, ui.visuals().weak_text_color());
+        painter.circle_stroke(center, ROSE_RADIUS - 1.0, stroke);
+        painter.hline(rect.x_range(), center.y, stroke);
+        painter.vline(center.x, rect.y_range(), stroke);
+
+        let sector = std::f32::consts::TAU / histogram.len() as f32;
+        for (index, weight) in histogram.iter().enumerate() {
+            if *weight <= 0.0 {
+                continue;
+            }
+            let radius = (ROSE_RADIUS - 1.0) * (weight / max_weight) as f32;
+            // Phase -π is at the left and angles grow counterclockwise, so y is flipped.
+            let point = |angle: f32| {
+                let angle = angle - std::f32::consts::PI;
+                center + radius * egui::vec2(angle.cos(), -angle.sin())
+            };
+            painter.add(Shape::convex_polygon(
+                vec![
+                    center,
+                    point(index as f32 * sector),
+                    point((index + 1) as f32 * sector),
+                ],
+                ui.visuals().selection.bg_fill,
+                Stroke::new(1.0, ui.visuals().selection.stroke.color),
+            ));
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use egui::DragValue;
+use egui_plot::{Line, PlotPoints};
+use num_complex::Complex;
+
+use super::TemplateApp;
+
+impl TemplateApp {
+    pub(super) fn phase_portrait_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        combined_wave: &[(f64, Complex<f64>)],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Time step (samples)");
+            ui.add(
+                DragValue::new(&mut self.phase_portrait_step)
+                    .speed(0.1)
+                    .range(1..=100),
+            );
+        });
+
+        let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+        let step = self.phase_portrait_step;
+        let dt = self.input_signal_range / self.num_samples.max(1) as f64;
+        let portrait = (step..samples.len().saturating_sub(step))
+            .map(|n| {
+                let derivative = (samples[n + step] - samples[n - step]) / (2.0 * step as f64 * dt);
+                [samples[n], derivative]
+            })
+            .collect::<PlotPoints>();
+
+        egui_plot::Plot::new("Phase Portrait Plot")
+            .x_axis_label("x")
+            .y_axis_label("dx/dt")
+            .show(ui, |plot_ui| plot_ui.line(Line::new(portrait)));
+    }
+}
@@ -0,0 +1,77 @@
+use egui::{Color32, Shape, Stroke};
+use num_complex::Complex;
+
+use super::{function_color, TemplateApp};
+
+const PIE_RADIUS: f32 = 50.0;
+/// Slices are split into pieces no wider than this so every polygon stays convex.
+const MAX_PIECE_ANGLE: f32 = std::f32::consts::FRAC_PI_2;
+const NOISE_FLOOR_COLOR: Color32 = Color32::GRAY;
+
+impl TemplateApp {
+    /// Pie chart of each function's share of the total signal power.
+    ///
+    /// A function contributes `A²/2` for its oscillation and `shift²` for its offset. Whatever
+    /// the combined wave has on top of that goes to the noise floor slice. With coherent summation
+    /// functions of the same frequency interfere, so the shares are only approximate.
+    pub(super) fn power_budget_ui(&self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        let total = combined_wave.iter().map(|(_, y)| y.re * y.re).sum::<f64>()
+            / combined_wave.len().max(1) as f64;
+        let mut slices = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(index, function)| {
+                let power = function.amplitude.0.powi(2) / 2.0 + function.y_shift.0.powi(2);
+                (
+                    format!("{} {}", function.function, index + 1),
+                    power,
+                    function_color(index),
+                )
+            })
+            .collect::<Vec<_>>();
+        let components = slices.iter().map(|(_, power, _)| power).sum::<f64>();
+        slices.push((
+            "Noise floor".to_owned(),
+            (total - components).max(0.0),
+            NOISE_FLOOR_COLOR,
+        ));
+        let sum = slices.iter().map(|(_, power, _)| power).sum::<f64>();
+        if sum <= 0.0 {
+            ui.label("The signal has no power.");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let (rect, _) =
+                ui.allocate_exact_size(egui::Vec2::splat(2.0 * PIE_RADIUS), egui::Sense::hover());
+            let painter = ui.painter_at(rect);
+            let center = rect.center();
+            let mut start = -std::f32::consts::FRAC_PI_2;
+            for (_, power, color) in &slices {
+                let angle = (power / sum) as f32 * std::f32::consts::TAU;
+                let pieces = (angle / MAX_PIECE_ANGLE).ceil() as usize;
+                for piece in 0..pieces {
+                    let from = start + angle * piece as f32 / pieces as f32;
+                    let to = start + angle * (piece + 1) as f32 / pieces as f32;
+                    let arc = (0..=8).map(|step| {
+                        let a = from + (to - from) * step as f32 / 8.0;
+                        center + PIE_RADIUS * egui::vec2(a.cos(), a.sin())
+                    });
+                    painter.add(Shape::convex_polygon(
+                        std::iter::once(center).chain(arc).collect(),
+                        *color,
+                        Stroke::NONE,
+                    ));
+                }
+                start += angle;
+            }
+
+            ui.vertical(|ui| {
+                for (name, power, color) in &slices {
+                    ui.colored_label(*color, format!("{name}: {:.1}%", power / sum * 100.0));
+                }
+            });
+        });
+    }
+}
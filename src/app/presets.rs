@@ -0,0 +1,144 @@
+use num_complex::Complex;
+
+use super::TemplateApp;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::signal_file::samples_to_csv;
+
+impl TemplateApp {
+    /// All settings in the same form as the saved session.
+    fn preset(&mut self) -> Result<String, String> {
+        self.with_synthetic_sampling(|app| {
+            ron::ser::to_string_pretty(app, ron::ser::PrettyConfig::default())
+                .map_err(|err| err.to_string())
+        })
+    }
+
+    /// Replaces all settings with the preset's. Transient state like an imported signal is
+    /// dropped, just like after a restart.
+    fn load_preset(&mut self, text: &str) -> Result<(), String> {
+        let preset = ron::from_str::<Self>(text).map_err(|err| err.to_string())?;
+        *self = Self {
+            show_presets: true,
+            ..preset.sanitized()
+        };
+        Ok(())
+    }
+
+    /// Writes the central panel's plots to `screenshot_path` once the screenshot requested by
+    /// the export button arrives.
+    pub(super) fn handle_screenshot(&mut self, ctx: &egui::Context) {
+        let Some(image) = ctx.input(|input| {
+            input.events.iter().find_map(|event| match event {
+                egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        }) else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let image = match self.plots_rect {
+                Some(rect) => image.region(&rect, Some(ctx.pixels_per_point())),
+                None => (*image).clone(),
+            };
+            self.export_error = write_png(&self.screenshot_path, &image).err();
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = image;
+    }
+
+    pub(super) fn presets_ui(&mut self, ui: &mut egui::Ui, combined_wave: &[(f64, Complex<f64>)]) {
+        ui.horizontal(|ui| {
+            if ui.button("Copy preset").clicked() {
+                match self.preset() {
+                    Ok(text) => ui.ctx().copy_text(text),
+                    Err(err) => self.export_error = Some(err),
+                }
+            }
+            if ui.button("Load pasted preset").clicked() {
+                let text = std::mem::take(&mut self.pasted_preset);
+                self.export_error = self.load_preset(&text).err();
+            }
+        });
+        ui.add(
+            egui::TextEdit::multiline(&mut self.pasted_preset)
+                .code_editor()
+                .desired_rows(3)
+                .hint_text("Paste a copied preset here"),
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        egui::Grid::new("export_paths")
+            .num_columns(3)
+            .show(ui, |ui| {
+                ui.label("Preset");
+                ui.text_edit_singleline(&mut self.preset_path);
+                ui.horizontal(|ui| {
+                    if ui.button("Save preset").clicked() {
+                        self.export_error = self
+                            .preset()
+                            .and_then(|text| {
+                                std::fs::write(&self.preset_path, text)
+                                    .map_err(|err| err.to_string())
+                            })
+                            .err();
+                    }
+                    if ui.button("Load preset").clicked() {
+                        self.export_error = std::fs::read_to_string(&self.preset_path)
+                            .map_err(|err| err.to_string())
+                            .and_then(|text| self.load_preset(&text))
+                            .err();
+                    }
+                });
+                ui.end_row();
+
+                ui.label("Samples");
+                ui.text_edit_singleline(&mut self.samples_path);
+                if ui
+                    .button("Export samples as CSV")
+                    .on_hover_text("One sample per line, so the file can be imported again")
+                    .clicked()
+                {
+                    let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+                    self.export_error =
+                        std::fs::write(&self.samples_path, samples_to_csv(&samples))
+                            .map_err(|err| err.to_string())
+                            .err();
+                }
+                ui.end_row();
+
+                ui.label("Plots");
+                ui.text_edit_singleline(&mut self.screenshot_path);
+                if ui.button("Export plots as PNG").clicked() {
+                    ui.ctx()
+                        .send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+                }
+                ui.end_row();
+            });
+        #[cfg(target_arch = "wasm32")]
+        let _ = combined_wave;
+
+        if let Some(error) = &self.export_error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+}
+
+/// Writes the image as an 8 bit RGBA PNG file.
+#[cfg(not(target_arch = "wasm32"))]
+fn write_png(path: &str, image: &egui::ColorImage) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|err| err.to_string())?;
+    let [width, height] = image.size;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data = image
+        .pixels
+        .iter()
+        .flat_map(|pixel| pixel.to_srgba_unmultiplied())
+        .collect::<Vec<_>>();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|err| err.to_string())
+}
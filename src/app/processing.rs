@@ -0,0 +1,388 @@
+use std::sync::Arc;
+
+use num_complex::Complex;
+
+use super::{
+    fir_design::{FirDesign, FirKey},
+    get_combined_wave_with_mode,
+    spectrogram::{Spectrogram, SpectrogramKey},
+    ChirpletKey, InputData, OrdFloat, SummationMode, TemplateApp,
+};
+use crate::{
+    fft::{fft, ifft},
+    gain_curve::GainCurve,
+    signal_flow::SignalFlow,
+    spectral_gate::{apply_soft_spectral_gate, apply_spectral_gate},
+    spectrum::{apply_band_mask, frequency_resolution},
+    weighting::{apply_frequency_weighting, FrequencyWeighting},
+    window::{apply_analysis_window, WindowFunction},
+};
+
+/// Shared samples of the combined wave, as returned by [`get_combined_wave_with_mode`].
+pub(super) type SharedWave = Arc<[(f64, Complex<f64>)]>;
+
+/// Shared FFT bins of the combined wave.
+pub(super) type SharedSpectrum = Arc<[Complex<f64>]>;
+
+/// Shared spectra of several parts of the combined wave.
+pub(super) type SharedSpectra = Arc<[Vec<Complex<f64>>]>;
+
+/// Everything the combined wave depends on.
+#[derive(PartialEq)]
+struct SignalKey {
+    functions: Vec<InputData>,
+    num_samples: usize,
+    input_signal_range: OrdFloat,
+    summation_mode: SummationMode,
+    window: WindowFunction,
+}
+
+/// Everything the filtered spectrum depends on besides the signal.
+#[derive(PartialEq)]
+struct FilterKey {
+    band_filter_enabled: bool,
+    band: (f64, f64),
+    gain_curve: Vec<(f64, f64)>,
+    gate_enabled: bool,
+    soft_gate: bool,
+    gate_threshold_db: f64,
+}
+
+/// A value computed from `K`, kept until it is asked for with a different `K`.
+///
+/// Keys of values derived from the signal start with [`SignalCache::generation`], so they are
+/// recomputed whenever the signal is.
+pub(super) struct Memo<K, V> {
+    entry: Option<(K, V)>,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self { entry: None }
+    }
+}
+
+impl<K: PartialEq, V: Clone> Memo<K, V> {
+    pub(super) fn get(&mut self, key: K, compute: impl FnOnce() -> V) -> V {
+        match &self.entry {
+            Some((cached, value)) if *cached == key => value.clone(),
+            _ => {
+                let value = compute();
+                self.entry = Some((key, value.clone()));
+                value
+            }
+        }
+    }
+}
+
+/// The bins after the band filter, the gain curve and the gate, and their inverse FFT.
+#[derive(Clone)]
+pub(super) struct Filtered {
+    pub(super) spectrum: SharedSpectrum,
+    pub(super) reconstruction: SharedSpectrum,
+}
+
+/// The weighted spectrum of the windowed wave for display, if a weighting is selected, and the
+/// weighted power of the wave itself.
+#[derive(Clone)]
+pub(super) struct Weighted {
+    pub(super) spectrum: Option<SharedSpectrum>,
+    pub(super) power: f64,
+}
+
+/// Every n-th sample of the combined wave and the spectrum of those samples.
+pub(super) type Subsampled = Arc<(Vec<(f64, Complex<f64>)>, Vec<Complex<f64>>)>;
+
+/// The combined wave, its spectra and what is derived from them, from the last time the signal
+/// or the settings of each result changed.
+#[derive(Default)]
+pub(super) struct SignalCache {
+    key: Option<SignalKey>,
+    /// Counts how often the signal was recomputed.
+    generation: u64,
+    combined_wave: SharedWave,
+    /// The spectrum of the windowed wave, for display.
+    spectrum: SharedSpectrum,
+    /// The spectrum of the wave itself, which the inverse FFT turns back into the wave.
+    unwindowed_spectrum: SharedSpectrum,
+    /// The levels of the stages from the source to the FFT.
+    signal_flow: SignalFlow,
+    gain_curve: Memo<Vec<(f64, f64)>, Arc<GainCurve>>,
+    filtered: Memo<(u64, FilterKey), Option<Filtered>>,
+    weighted: Memo<(u64, FrequencyWeighting), Weighted>,
+    segment_spectra: Memo<(u64, usize), SharedSpectra>,
+    subsampled: Memo<(u64, usize), Option<Subsampled>>,
+    peak_powers: Memo<u64, (f64, f64)>,
+    pub(super) spectrogram: Memo<(u64, SpectrogramKey), Option<Arc<Spectrogram>>>,
+    pub(super) chirplet: Memo<(u64, ChirpletKey), SharedSpectra>,
+    pub(super) chirplet_texture: Memo<(u64, ChirpletKey, usize), egui::TextureHandle>,
+    pub(super) fir_design: Memo<FirKey, Arc<FirDesign>>,
+    pub(super) fir_filtered: Memo<(u64, FirKey), Arc<[f64]>>,
+}
+
+impl SignalCache {
+    /// Forces the next [`TemplateApp::processed_signal`] to recompute, for changes the key
+    /// doesn't see.
+    pub(super) fn invalidate(&mut self) {
+        self.key = None;
+    }
+
+    /// Changes whenever the combined wave does.
+    pub(super) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The levels of the stages of the last wave up to its FFT, for the filter stages to be
+    /// recorded after.
+    pub(super) fn signal_flow(&self) -> SignalFlow {
+        self.signal_flow.clone()
+    }
+}
+
+impl TemplateApp {
+    /// The combined wave, the FFT of the windowed wave and the FFT of the wave itself, only
+    /// recomputed when the functions, the sampling or the window changed since the last frame.
+    pub(super) fn processed_signal(&mut self) -> (SharedWave, SharedSpectrum, SharedSpectrum) {
+        let key = SignalKey {
+            functions: self.functions.clone(),
+            num_samples: self.num_samples,
+            input_signal_range: self.input_signal_range.into(),
+            summation_mode: self.summation_mode,
+            window: self.window,
+        };
+        let cache = &mut self.signal_cache;
+        if cache.key.as_ref() != Some(&key) {
+            let combined_wave = match &self.imported {
+                Some(imported) => imported.wave(),
+                None => get_combined_wave_with_mode(
+                    self.functions.clone(),
+                    self.num_samples,
+                    self.input_signal_range,
+                    self.summation_mode,
+                ),
+            };
+            let mut signal_flow = SignalFlow::default();
+            if self.imported.is_some() {
+                signal_flow.record("Import", combined_wave.iter().map(|(_, y)| y.re));
+            } else {
+                signal_flow.record(
+                    "Functions",
+                    self.functions.iter().flat_map(|function| {
+                        combined_wave.iter().map(|(x, _)| function.value_at(*x))
+                    }),
+                );
+                signal_flow.record("Summation", combined_wave.iter().map(|(_, y)| y.re));
+            }
+
+            let mut unwindowed_spectrum = combined_wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+            fft(&mut unwindowed_spectrum);
+            let unwindowed_spectrum = SharedSpectrum::from(unwindowed_spectrum);
+            cache.spectrum = if self.window == WindowFunction::Rectangular {
+                signal_flow.bypass("Window");
+                unwindowed_spectrum.clone()
+            } else {
+                let mut spectrum = combined_wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+                apply_analysis_window(&mut spectrum, self.window);
+                signal_flow.record("Window", spectrum.iter().map(|y| y.re));
+                fft(&mut spectrum);
+                spectrum.into()
+            };
+            let normalization = 2.0 / cache.spectrum.len().max(1) as f64;
+            signal_flow.record(
+                "FFT",
+                cache.spectrum.iter().map(|y| y.norm() * normalization),
+            );
+
+            cache.combined_wave = combined_wave.into();
+            cache.unwindowed_spectrum = unwindowed_spectrum;
+            cache.signal_flow = signal_flow;
+            cache.key = Some(key);
+            cache.generation += 1;
+        }
+        (
+            cache.combined_wave.clone(),
+            cache.spectrum.clone(),
+            cache.unwindowed_spectrum.clone(),
+        )
+    }
+
+    /// The spline through the gain curve's control points, solved again only after an edit.
+    pub(super) fn gain_curve_spline(&mut self) -> Arc<GainCurve> {
+        self.signal_cache
+            .gain_curve
+            .get(self.gain_curve.clone(), || {
+                Arc::new(GainCurve::new(&self.gain_curve))
+            })
+    }
+
+    /// The filtered `unwindowed_spectrum` and its inverse FFT, or `None` without any filter.
+    pub(super) fn filtered_signal(
+        &mut self,
+        unwindowed_spectrum: &[Complex<f64>],
+    ) -> Option<Filtered> {
+        if self.gain_curve.is_empty() && !self.gate_enabled && !self.band_filter_enabled {
+            return None;
+        }
+        let gain_curve = self.gain_curve_spline();
+        let key = FilterKey {
+            band_filter_enabled: self.band_filter_enabled,
+            band: self.band,
+            gain_curve: self.gain_curve.clone(),
+            gate_enabled: self.gate_enabled,
+            soft_gate: self.soft_gate,
+            gate_threshold_db: self.gate_threshold_db,
+        };
+        let input_signal_range = self.input_signal_range;
+        self.signal_cache
+            .filtered
+            .get((self.signal_cache.generation, key), || {
+                // Filtered from the unwindowed spectrum so the inverse FFT gives back the wave.
+                let mut filtered = unwindowed_spectrum.to_vec();
+                if self.band_filter_enabled {
+                    apply_band_mask(&mut filtered, input_signal_range, self.band);
+                }
+                let freq_resolution = frequency_resolution(input_signal_range);
+                gain_curve.apply(&mut filtered, |k| k as f64 * freq_resolution);
+                if self.gate_enabled {
+                    let threshold_linear = 10f64.powf(self.gate_threshold_db / 20.0);
+                    if self.soft_gate {
+                        apply_soft_spectral_gate(&mut filtered, threshold_linear);
+                    } else {
+                        apply_spectral_gate(&mut filtered, threshold_linear);
+                    }
+                }
+                let mut reconstruction = filtered.clone();
+                ifft(&mut reconstruction);
+                Some(Filtered {
+                    spectrum: filtered.into(),
+                    reconstruction: reconstruction.into(),
+                })
+            })
+    }
+
+    /// The frequency weighting of `spectrum` and the weighted power of `unwindowed_spectrum`.
+    pub(super) fn weighted_spectrum(
+        &mut self,
+        spectrum: &[Complex<f64>],
+        unwindowed_spectrum: &[Complex<f64>],
+    ) -> Weighted {
+        let weighting = self.frequency_weighting;
+        let freq_resolution = frequency_resolution(self.input_signal_range);
+        self.signal_cache
+            .weighted
+            .get((self.signal_cache.generation, weighting), || {
+                let weighted =
+                    apply_frequency_weighting(unwindowed_spectrum, weighting, freq_resolution);
+                let n = weighted.len().max(1) as f64;
+                Weighted {
+                    spectrum: (weighting != FrequencyWeighting::None).then(|| {
+                        apply_frequency_weighting(spectrum, weighting, freq_resolution).into()
+                    }),
+                    power: weighted.iter().map(|bin| bin.norm_sqr()).sum::<f64>() / (n * n),
+                }
+            })
+    }
+
+    /// The windowed spectra of `segment_count` equal segments of `combined_wave`, or none for a
+    /// single segment.
+    pub(super) fn segment_spectra(
+        &mut self,
+        combined_wave: &[(f64, Complex<f64>)],
+    ) -> SharedSpectra {
+        let (segment_count, window) = (self.segment_count, self.window);
+        self.signal_cache
+            .segment_spectra
+            .get((self.signal_cache.generation, segment_count), || {
+                let segment_length = combined_wave.len() / segment_count.max(1);
+                if segment_count <= 1 || segment_length == 0 {
+                    return Arc::new([]);
+                }
+                combined_wave
+                    .chunks_exact(segment_length)
+                    .map(|segment| {
+                        let mut spectrum = segment.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+                        apply_analysis_window(&mut spectrum, window);
+                        fft(&mut spectrum);
+                        spectrum
+                    })
+                    .collect()
+            })
+    }
+
+    /// Every `display_subsample_factor`-th sample of `combined_wave` and its windowed spectrum.
+    pub(super) fn subsampled(
+        &mut self,
+        combined_wave: &[(f64, Complex<f64>)],
+    ) -> Option<Subsampled> {
+        let (factor, window) = (self.display_subsample_factor, self.window);
+        self.signal_cache
+            .subsampled
+            .get((self.signal_cache.generation, factor), || {
+                (factor > 1).then(|| {
+                    let samples = combined_wave
+                        .iter()
+                        .step_by(factor)
+                        .copied()
+                        .collect::<Vec<_>>();
+                    let mut spectrum = samples.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+                    apply_analysis_window(&mut spectrum, window);
+                    fft(&mut spectrum);
+                    Arc::new((samples, spectrum))
+                })
+            })
+    }
+
+    /// The peak power of the functions summed coherently and incoherently.
+    pub(super) fn peak_powers(&mut self) -> (f64, f64) {
+        let peak_power = |mode| {
+            get_combined_wave_with_mode(
+                self.functions.clone(),
+                self.num_samples,
+                self.input_signal_range,
+                mode,
+            )
+            .iter()
+            .map(|(_, y)| y.re * y.re)
+            .fold(0.0, f64::max)
+        };
+        self.signal_cache
+            .peak_powers
+            .get(self.signal_cache.generation, || {
+                (
+                    peak_power(SummationMode::Coherent),
+                    peak_power(SummationMode::Incoherent),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::PeriodicFunction;
+
+    #[test]
+    fn filtered_signal_is_only_recomputed_after_a_change() {
+        let mut app = TemplateApp {
+            functions: vec![InputData::tone(PeriodicFunction::Sin, 1.0, 10.0)],
+            gain_curve: vec![(0.0, 0.0), (100.0, -20.0)],
+            ..TemplateApp::initial()
+        };
+        let (_, _, unwindowed_spectrum) = app.processed_signal();
+        let first = app.filtered_signal(&unwindowed_spectrum).unwrap();
+        let again = app.filtered_signal(&unwindowed_spectrum).unwrap();
+        assert!(Arc::ptr_eq(&first.reconstruction, &again.reconstruction));
+
+        app.gain_curve[1].1 = -40.0;
+        let edited = app.filtered_signal(&unwindowed_spectrum).unwrap();
+        assert!(!Arc::ptr_eq(&first.reconstruction, &edited.reconstruction));
+
+        app.functions[0].amplitude = 2.0.into();
+        let (_, _, unwindowed_spectrum) = app.processed_signal();
+        let rescaled = app.filtered_signal(&unwindowed_spectrum).unwrap();
+        assert!(!Arc::ptr_eq(
+            &edited.reconstruction,
+            &rescaled.reconstruction
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use std::f64::consts::TAU;
+
+use egui::DragValue;
+
+use super::{InputData, PeriodicFunction, TemplateApp};
+
+const MIN_FUNCTIONS: u64 = 2;
+const MAX_FUNCTIONS: u64 = 6;
+/// At least one function gets an amplitude of at least this much.
+const MIN_PEAK_AMPLITUDE: f64 = 0.5;
+/// The function types to pick from, fixed so a seed keeps giving the same signal.
+const FUNCTIONS: [PeriodicFunction; 4] = [
+    PeriodicFunction::Sin,
+    PeriodicFunction::Cos,
+    PeriodicFunction::Square,
+    PeriodicFunction::Sawtooth,
+];
+
+/// SplitMix64, which is plenty for picking parameters and keeps a seed reproducible everywhere.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `min..max`.
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        min + unit * (max - min)
+    }
+}
+
+/// Two to six functions with random type, amplitude, frequency and phase.
+fn random_functions(seed: u64) -> Vec<InputData> {
+    let mut rng = SplitMix64(seed);
+    let count = MIN_FUNCTIONS + rng.next_u64() % (MAX_FUNCTIONS - MIN_FUNCTIONS + 1);
+    let mut functions = (0..count)
+        .map(|_| {
+            let index = rng.next_u64() as usize % FUNCTIONS.len();
+            InputData {
+                function: FUNCTIONS[index].clone(),
+                amplitude: rng.range(0.1, 2.0).into(),
+                frequency: rng.range(0.5, 20.0).into(),
+                y_shift: 0.0.into(),
+                phase: rng.range(0.0, TAU).into(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if let Some(loudest) = functions
+        .iter_mut()
+        .max_by(|a, b| a.amplitude.cmp(&b.amplitude))
+    {
+        loudest.amplitude.0 = loudest.amplitude.0.max(MIN_PEAK_AMPLITUDE);
+    }
+    functions
+}
+
+impl TemplateApp {
+    pub(super) fn randomizer_ui(&mut self, ui: &mut egui::Ui) {
+        ui.add_enabled_ui(!self.random_frozen, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Random seed");
+                let seed_changed = ui.add(DragValue::new(&mut self.random_seed)).changed();
+                let next = ui.button("Next random").clicked();
+                if next {
+                    self.random_seed = self.random_seed.wrapping_add(1);
+                }
+                if ui.button("Randomize").clicked() || seed_changed || next {
+                    self.functions = random_functions(self.random_seed);
+                }
+            });
+        });
+        ui.checkbox(&mut self.random_frozen, "Freeze")
+            .on_hover_text("Keep the current configuration while the seed is locked");
+    }
+}
@@ -0,0 +1,426 @@
+use std::time::Duration;
+
+use egui::DragValue;
+
+use super::{InputData, OrdFloat, PeriodicFunction, SummationMode, TemplateApp};
+use crate::window::WindowFunction;
+
+/// A single edit of the app's signal parameters.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub(super) enum ParameterChange {
+    AddFunction(InputData),
+    RemoveFunction(usize),
+    Function(usize, PeriodicFunction),
+    Amplitude(usize, f64),
+    Frequency(usize, f64),
+    YShift(usize, f64),
+    Phase(usize, f64),
+    NumSamples(usize),
+    InputSignalRange(f64),
+    SummationMode(SummationMode),
+    Window(WindowFunction),
+    BandFilterEnabled(bool),
+    Band(f64, f64),
+    GainCurve(Vec<(f64, f64)>),
+    GateEnabled(bool),
+    SoftGate(bool),
+    GateThresholdDb(f64),
+}
+
+type FieldChange = fn(usize, f64) -> ParameterChange;
+
+/// The parameters a recording starts from and that changes are applied to.
+///
+/// Fields missing from older recordings get their defaults, which `restore` then sanitizes.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(super) struct Snapshot {
+    functions: Vec<InputData>,
+    num_samples: usize,
+    input_signal_range: f64,
+    summation_mode: SummationMode,
+    window: WindowFunction,
+    band_filter_enabled: bool,
+    band: (f64, f64),
+    gain_curve: Vec<(f64, f64)>,
+    gate_enabled: bool,
+    soft_gate: bool,
+    gate_threshold_db: f64,
+}
+
+impl Snapshot {
+    fn of(app: &TemplateApp) -> Self {
+        Self {
+            functions: app.functions.clone(),
+            num_samples: app.num_samples,
+            input_signal_range: app.input_signal_range,
+            summation_mode: app.summation_mode,
+            window: app.window,
+            band_filter_enabled: app.band_filter_enabled,
+            band: app.band,
+            gain_curve: app.gain_curve.clone(),
+            gate_enabled: app.gate_enabled,
+            soft_gate: app.soft_gate,
+            gate_threshold_db: app.gate_threshold_db,
+        }
+    }
+
+    /// Sets the app's parameters to the snapshot's, sanitized like a loaded preset.
+    fn restore(self, app: &mut TemplateApp) {
+        app.functions = self.functions;
+        app.num_samples = self.num_samples;
+        app.input_signal_range = self.input_signal_range;
+        app.summation_mode = self.summation_mode;
+        app.window = self.window;
+        app.band_filter_enabled = self.band_filter_enabled;
+        app.band = self.band;
+        app.gain_curve = self.gain_curve;
+        app.gate_enabled = self.gate_enabled;
+        app.soft_gate = self.soft_gate;
+        app.gate_threshold_db = self.gate_threshold_db;
+        app.sanitize();
+    }
+
+    /// Changes that turn `self` into `other`.
+    fn diff(&self, other: &Snapshot) -> Vec<ParameterChange> {
+        let mut changes = Vec::new();
+        for (index, (old, new)) in self
+            .functions
+            .iter()
+            .zip(other.functions.iter())
+            .enumerate()
+        {
+            if old.function != new.function {
+                changes.push(ParameterChange::Function(index, new.function.clone()));
+            }
+            let fields: [(OrdFloat, OrdFloat, FieldChange); 4] = [
+                (old.amplitude, new.amplitude, ParameterChange::Amplitude),
+                (old.frequency, new.frequency, ParameterChange::Frequency),
+                (old.y_shift, new.y_shift, ParameterChange::YShift),
+                (old.phase, new.phase, ParameterChange::Phase),
+            ];
+            for (old, new, change) in fields {
+                if old != new {
+                    changes.push(change(index, new.0));
+                }
+            }
+        }
+        for function in other.functions.iter().skip(self.functions.len()) {
+            changes.push(ParameterChange::AddFunction(function.clone()));
+        }
+        for index in (other.functions.len()..self.functions.len()).rev() {
+            changes.push(ParameterChange::RemoveFunction(index));
+        }
+        if self.num_samples != other.num_samples {
+            changes.push(ParameterChange::NumSamples(other.num_samples));
+        }
+        if self.input_signal_range.to_bits() != other.input_signal_range.to_bits() {
+            changes.push(ParameterChange::InputSignalRange(other.input_signal_range));
+        }
+        if self.summation_mode != other.summation_mode {
+            changes.push(ParameterChange::SummationMode(other.summation_mode));
+        }
+        if self.window != other.window {
+            changes.push(ParameterChange::Window(other.window));
+        }
+        if self.band_filter_enabled != other.band_filter_enabled {
+            changes.push(ParameterChange::BandFilterEnabled(
+                other.band_filter_enabled,
+            ));
+        }
+        if (self.band.0.to_bits(), self.band.1.to_bits())
+            != (other.band.0.to_bits(), other.band.1.to_bits())
+        {
+            changes.push(ParameterChange::Band(other.band.0, other.band.1));
+        }
+        if self.gain_curve != other.gain_curve {
+            changes.push(ParameterChange::GainCurve(other.gain_curve.clone()));
+        }
+        if self.gate_enabled != other.gate_enabled {
+            changes.push(ParameterChange::GateEnabled(other.gate_enabled));
+        }
+        if self.soft_gate != other.soft_gate {
+            changes.push(ParameterChange::SoftGate(other.soft_gate));
+        }
+        if self.gate_threshold_db.to_bits() != other.gate_threshold_db.to_bits() {
+            changes.push(ParameterChange::GateThresholdDb(other.gate_threshold_db));
+        }
+        changes
+    }
+
+    fn apply(&mut self, change: &ParameterChange) {
+        match change {
+            ParameterChange::AddFunction(new) => self.functions.push(new.clone()),
+            ParameterChange::RemoveFunction(index) => {
+                if *index < self.functions.len() {
+                    self.functions.remove(*index);
+                }
+            }
+            ParameterChange::Function(index, new) => {
+                if let Some(f) = self.functions.get_mut(*index) {
+                    f.function = new.clone();
+                }
+            }
+            ParameterChange::Amplitude(index, value) => {
+                if let Some(f) = self.functions.get_mut(*index) {
+                    f.amplitude = (*value).into();
+                }
+            }
+            ParameterChange::Frequency(index, value) => {
+                if let Some(f) = self.functions.get_mut(*index) {
+                    f.frequency = (*value).into();
+                }
+            }
+            ParameterChange::YShift(index, value) => {
+                if let Some(f) = self.functions.get_mut(*index) {
+                    f.y_shift = (*value).into();
+                }
+            }
+            ParameterChange::Phase(index, value) => {
+                if let Some(f) = self.functions.get_mut(*index) {
+                    f.phase = (*value).into();
+                }
+            }
+            ParameterChange::NumSamples(value) => self.num_samples = *value,
+            ParameterChange::InputSignalRange(value) => self.input_signal_range = *value,
+            ParameterChange::SummationMode(mode) => self.summation_mode = *mode,
+            ParameterChange::Window(window) => self.window = *window,
+            ParameterChange::BandFilterEnabled(enabled) => self.band_filter_enabled = *enabled,
+            ParameterChange::Band(low, high) => self.band = (*low, *high),
+            ParameterChange::GainCurve(points) => self.gain_curve = points.clone(),
+            ParameterChange::GateEnabled(enabled) => self.gate_enabled = *enabled,
+            ParameterChange::SoftGate(soft) => self.soft_gate = *soft,
+            ParameterChange::GateThresholdDb(value) => self.gate_threshold_db = *value,
+        }
+    }
+}
+
+/// A recorded sequence of parameter changes that can be written to and read from a file.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub(super) struct Recording {
+    initial: Snapshot,
+    changes: Vec<(Duration, ParameterChange)>,
+}
+
+impl Recording {
+    fn duration(&self) -> Duration {
+        self.changes
+            .last()
+            .map_or(Duration::ZERO, |(time, _)| *time)
+    }
+}
+
+#[derive(Default)]
+pub(super) enum RecorderMode {
+    #[default]
+    Idle,
+    Recording {
+        started_at: f64,
+        last: Snapshot,
+    },
+    Replaying {
+        position: Duration,
+        paused: bool,
+    },
+}
+
+pub(super) struct Recorder {
+    recording: Recording,
+    mode: RecorderMode,
+    speed: f64,
+    path: String,
+    error: Option<String>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Self {
+            recording: Recording::default(),
+            mode: RecorderMode::default(),
+            speed: 1.0,
+            path: "recording.ron".to_owned(),
+            error: None,
+        }
+    }
+}
+
+impl TemplateApp {
+    /// Records the changes made since the last frame or drives the parameters during a replay.
+    pub(super) fn update_recorder(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+        let current = Snapshot::of(self);
+
+        match &mut self.recorder.mode {
+            RecorderMode::Idle => {}
+            RecorderMode::Recording { started_at, last } => {
+                let time = Duration::from_secs_f64(now - *started_at);
+                for change in last.diff(&current) {
+                    self.recorder.recording.changes.push((time, change));
+                }
+                *last = current;
+            }
+            RecorderMode::Replaying { position, paused } => {
+                let duration = self.recorder.recording.duration();
+                let mut finished = false;
+                if !*paused {
+                    let dt = ctx.input(|i| i.stable_dt) as f64 * self.recorder.speed;
+                    *position += Duration::from_secs_f64(dt);
+                    if *position >= duration {
+                        *position = duration;
+                        finished = true;
+                    }
+                    ctx.request_repaint();
+                }
+
+                let mut state = self.recorder.recording.initial.clone();
+                for (_, change) in self
+                    .recorder
+                    .recording
+                    .changes
+                    .iter()
+                    .take_while(|(time, _)| time <= position)
+                {
+                    state.apply(change);
+                }
+                // A recording can come from a file, so it gets the same checks as a preset.
+                state.restore(self);
+                // The final state stays, but later edits are the user's again.
+                if finished {
+                    self.recorder.mode = RecorderMode::Idle;
+                }
+            }
+        }
+    }
+
+    pub(super) fn recorder_ui(&mut self, ui: &mut egui::Ui) {
+        let now = ui.input(|i| i.time);
+        let snapshot = Snapshot::of(self);
+        let recorder = &mut self.recorder;
+
+        ui.horizontal(|ui| match &mut recorder.mode {
+            RecorderMode::Idle => {
+                if ui.button("⏺ Record").clicked() {
+                    recorder.recording = Recording {
+                        initial: snapshot.clone(),
+                        changes: Vec::new(),
+                    };
+                    recorder.mode = RecorderMode::Recording {
+                        started_at: now,
+                        last: snapshot,
+                    };
+                }
+                let can_replay = !recorder.recording.changes.is_empty();
+                if ui
+                    .add_enabled(can_replay, egui::Button::new("▶ Replay"))
+                    .clicked()
+                {
+                    recorder.mode = RecorderMode::Replaying {
+                        position: Duration::ZERO,
+                        paused: false,
+                    };
+                }
+            }
+            RecorderMode::Recording { started_at, .. } => {
+                ui.label(format!("Recording… {:.1} s", now - *started_at));
+                if ui.button("⏹ Stop").clicked() {
+                    recorder.mode = RecorderMode::Idle;
+                }
+            }
+            RecorderMode::Replaying { position, paused } => {
+                let label = if *paused { "▶ Resume" } else { "⏸ Pause" };
+                if ui.button(label).clicked() {
+                    *paused = !*paused;
+                }
+                if ui.button("⏹ Stop").clicked() {
+                    recorder.mode = RecorderMode::Idle;
+                    return;
+                }
+
+                let mut seconds = position.as_secs_f64();
+                let duration = recorder.recording.duration().as_secs_f64();
+                let timeline = ui.add(
+                    egui::Slider::new(&mut seconds, 0.0..=duration)
+                        .suffix(" s")
+                        .text("Timeline"),
+                );
+                if timeline.changed() {
+                    *position = Duration::from_secs_f64(seconds);
+                    *paused = true;
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Replay speed");
+            ui.add(
+                DragValue::new(&mut recorder.speed)
+                    .speed(0.05)
+                    .range(0.1..=10.0)
+                    .suffix("×"),
+            );
+            ui.label(format!("{} changes", recorder.recording.changes.len()));
+        });
+
+        #[cfg(not(target_arch = "wasm32"))]
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut recorder.path);
+            if ui.button("Export").clicked() {
+                recorder.error = ron::ser::to_string_pretty(
+                    &recorder.recording,
+                    ron::ser::PrettyConfig::default(),
+                )
+                .map_err(|err| err.to_string())
+                .and_then(|text| {
+                    std::fs::write(&recorder.path, text).map_err(|err| err.to_string())
+                })
+                .err();
+            }
+            if ui.button("Import").clicked() {
+                match std::fs::read_to_string(&recorder.path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()))
+                {
+                    Ok(recording) => {
+                        recorder.recording = recording;
+                        recorder.mode = RecorderMode::Idle;
+                        recorder.error = None;
+                    }
+                    Err(err) => recorder.error = Some(err),
+                }
+            }
+        });
+
+        if let Some(error) = &recorder.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_hands_the_parameters_back_at_its_end() {
+        let mut app = TemplateApp::initial();
+        app.recorder.recording = Recording {
+            initial: Snapshot::of(&app),
+            changes: vec![(Duration::from_millis(10), ParameterChange::NumSamples(512))],
+        };
+        app.recorder.mode = RecorderMode::Replaying {
+            position: Duration::ZERO,
+            paused: false,
+        };
+
+        let ctx = egui::Context::default();
+        for _ in 0..10 {
+            app.update_recorder(&ctx);
+        }
+        assert!(matches!(app.recorder.mode, RecorderMode::Idle));
+        assert_eq!(app.num_samples, 512);
+
+        app.num_samples = 2048;
+        app.update_recorder(&ctx);
+        assert_eq!(app.num_samples, 2048);
+    }
+}
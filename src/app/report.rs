@@ -0,0 +1,188 @@
+use std::fmt::Write as _;
+
+use num_complex::Complex;
+
+use super::{resynthesis_snr, TemplateApp};
+use crate::{
+    analysis::{pick_peaks, signal_bandwidth},
+    spectrum::frequency_resolution,
+};
+
+/// Builds a Markdown summary of the current signal and its spectrum.
+///
+/// The peaks and the bandwidth come from `fft_result`, the spectrum with the analysis window like
+/// on the frequency plot. The Parseval check needs `unwindowed_spectrum`, and `reconstruction`
+/// is the filtered resynthesis, if any, for the same SNR as the statistics panel.
+pub(super) fn generate_report(
+    app: &TemplateApp,
+    combined_wave: &[(f64, Complex<f64>)],
+    fft_result: &[Complex<f64>],
+    unwindowed_spectrum: &[Complex<f64>],
+    reconstruction: Option<&[Complex<f64>]>,
+) -> String {
+    let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+    let n = samples.len();
+    let sample_rate = app.num_samples as f64 / app.input_signal_range;
+    let freq_resolution = frequency_resolution(app.input_signal_range);
+    let normalization = 2.0 / n.max(1) as f64;
+    let magnitudes = fft_result[..fft_result.len() / 2]
+        .iter()
+        .map(|bin| bin.norm())
+        .collect::<Vec<_>>();
+
+    let mut report = String::new();
+    let _ = writeln!(report, "# Spectral analysis report\n");
+
+    let _ = writeln!(report, "## Signal parameters\n");
+    let _ = writeln!(report, "- Duration: {:.4}", app.input_signal_range);
+    let _ = writeln!(report, "- Sample rate: {sample_rate:.4} Hz");
+    let _ = writeln!(report, "- Number of samples (N): {n}");
+    let _ = writeln!(report, "- Window function: {}", app.window);
+    let _ = writeln!(report, "- Functions:");
+    for (index, function) in app.functions.iter().enumerate() {
+        let _ = writeln!(
+            report,
+            "  {}. {} (amplitude {}, frequency {}, y shift {}, phase {})",
+            index + 1,
+            function.function,
+            function.amplitude.0,
+            function.frequency.0,
+            function.y_shift.0,
+            function.phase.0,
+        );
+    }
+
+    let _ = writeln!(report, "\n## Detected peaks\n");
+    let max_magnitude = magnitudes.iter().copied().fold(0.0, f64::max);
+    let peaks = pick_peaks(&magnitudes, 0.1 * max_magnitude);
+    if peaks.is_empty() {
+        let _ = writeln!(report, "No peaks detected.");
+    } else {
+        let _ = writeln!(report, "| Frequency (Hz) | Magnitude | Phase (rad) |");
+        let _ = writeln!(report, "|---|---|---|");
+        for k in peaks {
+            let _ = writeln!(
+                report,
+                "| {:.4} | {:.4} | {:.4} |",
+                k as f64 * freq_resolution,
+                magnitudes[k] * normalization,
+                fft_result[k].arg(),
+            );
+        }
+    }
+
+    let rms = (samples.iter().map(|x| x * x).sum::<f64>() / n.max(1) as f64).sqrt();
+    let (lower, upper) = signal_bandwidth(
+        &magnitudes,
+        freq_resolution,
+        app.bandwidth_power_percent / 100.0,
+    );
+    let _ = writeln!(report, "\n## Statistics\n");
+    let _ = writeln!(report, "- RMS: {rms:.4}");
+    match resynthesis_snr(combined_wave, reconstruction) {
+        Some(snr) => {
+            let _ = writeln!(report, "- Resynthesis SNR: {snr:.1} dB");
+        }
+        None => {
+            let _ = writeln!(report, "- Resynthesis SNR: n/a (no filter is active)");
+        }
+    }
+    let _ = writeln!(
+        report,
+        "- Bandwidth ({:.0} % power): {:.4} Hz ({lower:.4}–{upper:.4} Hz)",
+        app.bandwidth_power_percent,
+        upper - lower,
+    );
+
+    let mut warnings = Vec::new();
+    let nyquist = sample_rate / 2.0;
+    for (index, function) in app.functions.iter().enumerate() {
+        let frequency_hz = function.frequency.0.abs();
+        if frequency_hz > nyquist {
+            warnings.push(format!(
+                "Function {} ({frequency_hz:.2} Hz) is above the Nyquist frequency ({nyquist:.2} Hz) and aliases.",
+                index + 1
+            ));
+        }
+    }
+    let time_energy = samples.iter().map(|x| x * x).sum::<f64>();
+    let frequency_energy = unwindowed_spectrum
+        .iter()
+        .map(|x| x.norm_sqr())
+        .sum::<f64>()
+        / unwindowed_spectrum.len().max(1) as f64;
+    if time_energy > 0.0 {
+        let parseval_error = (time_energy - frequency_energy).abs() / time_energy;
+        if parseval_error > 1e-6 {
+            warnings.push(format!(
+                "Parseval's theorem is violated by {:.2} %.",
+                parseval_error * 100.0
+            ));
+        }
+    }
+    if let [first, .., second_last, last] = samples.as_slice() {
+        let peak_to_peak = samples.iter().copied().fold(f64::MIN, f64::max)
+            - samples.iter().copied().fold(f64::MAX, f64::min);
+        let continuation = 2.0 * last - second_last;
+        if peak_to_peak > 0.0 && (continuation - first).abs() > 0.1 * peak_to_peak {
+            warnings.push(
+                "The signal does not wrap around smoothly, expect spectral leakage.".to_owned(),
+            );
+        }
+    }
+
+    let _ = writeln!(report, "\n## Warnings\n");
+    if warnings.is_empty() {
+        let _ = writeln!(report, "None.");
+    }
+    for warning in warnings {
+        let _ = writeln!(report, "- {warning}");
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::{get_combined_wave, InputData, PeriodicFunction},
+        fft::fft,
+        window::WindowFunction,
+    };
+
+    #[test]
+    fn report_of_a_single_tone() {
+        let app = TemplateApp {
+            functions: vec![InputData {
+                frequency: 10.0.into(),
+                ..InputData::new(PeriodicFunction::Sin)
+            }],
+            num_samples: 1000,
+            input_signal_range: 1.0,
+            ..TemplateApp::initial()
+        };
+        let combined_wave = get_combined_wave(app.functions.clone(), 1000, 1.0);
+        let mut spectrum = combined_wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+
+        // Without a window the windowed and unwindowed spectra are the same.
+        assert_eq!(app.window, WindowFunction::Rectangular);
+        let report = generate_report(&app, &combined_wave, &spectrum, &spectrum, None);
+        for expected in [
+            "# Spectral analysis report",
+            "- Sample rate: 1000.0000 Hz",
+            "- Number of samples (N): 1000",
+            "1. Sin (amplitude 1, frequency 10, y shift 0, phase 0)",
+            "| 10.0000 | 1.0000 |",
+            "- RMS: 0.7071",
+            "- Resynthesis SNR: n/a",
+            "## Warnings\n\nNone.",
+        ] {
+            assert!(
+                report.contains(expected),
+                "{expected:?} missing in\n{report}"
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use egui_plot::{Bar, BarChart};
+use num_complex::Complex;
+
+use super::{get_combined_wave_with_mode, InputData, TemplateApp};
+use crate::{fft::fft, window::apply_analysis_window};
+
+/// Sensitivity analysis is skipped above this many samples since it runs many FFTs per frame.
+const MAX_SAMPLES: usize = 4096;
+const DELTA: f64 = 1e-4;
+
+type FieldAccessor = fn(&mut InputData) -> &mut f64;
+
+impl TemplateApp {
+    pub(super) fn sensitivity_ui(&self, ui: &mut egui::Ui) {
+        if self.imported.is_some() {
+            ui.label("Sensitivity analysis needs the function parameters, not an imported signal.");
+            return;
+        }
+        if self.num_samples > MAX_SAMPLES {
+            ui.label(format!(
+                "Sensitivity analysis is only available for up to {MAX_SAMPLES} samples."
+            ));
+            return;
+        }
+        if self.functions.is_empty() {
+            ui.label("Add a function to analyse its sensitivity.");
+            return;
+        }
+
+        // The same processing as the main spectrum, so the numbers match the frequency plot.
+        let spectrum = |functions: Vec<InputData>| {
+            let mut spectrum = get_combined_wave_with_mode(
+                functions,
+                self.num_samples,
+                self.input_signal_range,
+                self.summation_mode,
+            )
+            .into_iter()
+            .map(|(_, y)| y)
+            .collect::<Vec<Complex<f64>>>();
+            apply_analysis_window(&mut spectrum, self.window);
+            fft(&mut spectrum);
+            spectrum
+        };
+
+        let baseline = spectrum(self.functions.clone());
+        let peak_bin = baseline[..baseline.len() / 2]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.norm().total_cmp(&b.1.norm()))
+            .map_or(0, |(k, _)| k);
+        ui.label(format!("Derivatives of |X[{peak_bin}]| (dominant peak)"));
+
+        let fields: [(&str, FieldAccessor); 3] = [
+            ("amplitude", |f| &mut f.amplitude.0),
+            ("frequency", |f| &mut f.frequency.0),
+            ("phase", |f| &mut f.phase.0),
+        ];
+        let mut sensitivities = Vec::new();
+        for index in 0..self.functions.len() {
+            for (name, field) in fields {
+                let peak_with_offset = |offset: f64| {
+                    let mut functions = self.functions.clone();
+                    *field(&mut functions[index]) += offset;
+                    spectrum(functions)
+                        .get(peak_bin)
+                        .map_or(0.0, |bin| bin.norm())
+                };
+                let derivative =
+                    (peak_with_offset(DELTA) - peak_with_offset(-DELTA)) / (2.0 * DELTA);
+                sensitivities.push((format!("#{} {name}", index + 1), derivative));
+            }
+        }
+
+        let most_sensitive = sensitivities
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1 .1.abs().total_cmp(&b.1 .1.abs()))
+            .map(|(index, _)| index);
+        let bars = sensitivities
+            .iter()
+            .enumerate()
+            .map(|(index, (name, derivative))| {
+                let bar = Bar::new(index as f64, *derivative).name(name);
+                if Some(index) == most_sensitive {
+                    bar.fill(ui.visuals().selection.bg_fill)
+                } else {
+                    bar
+                }
+            })
+            .collect();
+
+        let labels = sensitivities
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        egui_plot::Plot::new("Sensitivity Plot")
+            .x_axis_formatter(move |mark, _range| {
+                let index = mark.value.round();
+                if (mark.value - index).abs() < f64::EPSILON && index >= 0.0 {
+                    labels.get(index as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(bars)));
+    }
+}
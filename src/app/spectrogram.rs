@@ -0,0 +1,311 @@
+use std::sync::Arc;
+
+use egui::{ComboBox, DragValue};
+use egui_plot::{Line, PlotImage, PlotPoint, PlotPoints, Points};
+use num_complex::Complex;
+
+use super::TemplateApp;
+use crate::{
+    heatmap::{colormap, decibel_heatmap_image, decibel_level, heatmap_image},
+    stft::{reassignment_stfts, spectral_reassignment, track_spectral_peaks, windowed_stft},
+    window::WindowFunction,
+};
+
+/// Signals with at least this many samples have too many frames for the overlap diagram.
+const MAX_DIAGRAM_SAMPLES: usize = 100;
+const DIAGRAM_ROW_HEIGHT: f32 = 8.0;
+const DIAGRAM_ROW_SPACING: f32 = 2.0;
+/// Reassigned points are drawn in this many brightness levels.
+const REASSIGNED_LEVELS: usize = 16;
+/// Reassigned points below this fraction of the brightest level are left out.
+const REASSIGNED_FLOOR: f64 = 0.01;
+
+/// The settings the spectrogram depends on besides the signal.
+#[derive(Clone, Copy, PartialEq)]
+pub(super) struct SpectrogramKey {
+    frame_size: usize,
+    hop_size: usize,
+    window: WindowFunction,
+    decibels: bool,
+    dynamic_range_db: f64,
+    reassign: bool,
+    track_peaks: bool,
+    max_peak_gap_bins: usize,
+}
+
+/// The STFT of the signal and everything drawn from it.
+pub(super) struct Spectrogram {
+    frame_size: usize,
+    num_frames: usize,
+    texture: egui::TextureHandle,
+    /// Points of the reassigned spectrogram grouped by brightness.
+    reassigned: Vec<Vec<[f64; 2]>>,
+    tracks: Vec<Vec<(f64, f64)>>,
+}
+
+impl TemplateApp {
+    pub(super) fn spectrogram_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        combined_wave: &[(f64, Complex<f64>)],
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("Frame size");
+            ui.add(
+                DragValue::new(&mut self.stft_frame_size)
+                    .speed(1.0)
+                    .range(2..=4096),
+            );
+            ui.label("Hop size");
+            ui.add(
+                DragValue::new(&mut self.stft_hop_size)
+                    .speed(1.0)
+                    .range(1..=4096),
+            );
+            ui.label("Window");
+            let spectrum_window = format!("Same as spectrum ({})", self.window);
+            ComboBox::from_id_salt("spectrogram_window")
+                .selected_text(match self.spectrogram_window {
+                    Some(window) => window.to_string(),
+                    None => spectrum_window.clone(),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.spectrogram_window, None, spectrum_window);
+                    for window in WindowFunction::ALL {
+                        ui.selectable_value(
+                            &mut self.spectrogram_window,
+                            Some(window),
+                            window.to_string(),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text("The reassigned spectrogram always uses the Hann window");
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.spectrogram_decibels, "Decibels");
+            if self.spectrogram_decibels {
+                ui.label("Dynamic range");
+                ui.add(
+                    DragValue::new(&mut self.spectrogram_dynamic_range_db)
+                        .speed(1.0)
+                        .range(1.0..=200.0)
+                        .suffix(" dB"),
+                );
+            }
+            ui.checkbox(&mut self.reassign_spectrogram, "Reassigned")
+                .on_hover_text(
+                    "Move every bin's energy to where it is centered in time and frequency",
+                );
+            ui.checkbox(&mut self.track_peaks, "Track peaks");
+            if self.track_peaks {
+                ui.label("Max gap (bins)");
+                ui.add(DragValue::new(&mut self.max_peak_gap_bins).range(0..=64));
+            }
+        });
+
+        let key = SpectrogramKey {
+            frame_size: self.stft_frame_size,
+            hop_size: self.stft_hop_size,
+            window: self.spectrogram_window.unwrap_or(self.window),
+            decibels: self.spectrogram_decibels,
+            dynamic_range_db: self.spectrogram_dynamic_range_db,
+            reassign: self.reassign_spectrogram,
+            track_peaks: self.track_peaks,
+            max_peak_gap_bins: self.max_peak_gap_bins,
+        };
+        let dt = self.input_signal_range / self.num_samples.max(1) as f64;
+        let generation = self.signal_cache.generation();
+        let Some(spectrogram) = self.signal_cache.spectrogram.get((generation, key), || {
+            compute_spectrogram(ui.ctx(), combined_wave, &key, dt)
+        }) else {
+            ui.label("The signal is shorter than one frame.");
+            return;
+        };
+        let frame_size = spectrogram.frame_size;
+
+        ui.horizontal(|ui| {
+            let overlap = frame_size.saturating_sub(self.stft_hop_size) as f64 / frame_size as f64;
+            ui.label(format!("Overlap: {:.1}%", overlap * 100.0));
+            ui.label(format!("Number of frames: {}", spectrogram.num_frames));
+        });
+        let diagram_height = if combined_wave.len() < MAX_DIAGRAM_SAMPLES {
+            spectrogram.num_frames as f32 * (DIAGRAM_ROW_HEIGHT + DIAGRAM_ROW_SPACING)
+        } else {
+            ui.spacing().interact_size.y
+        };
+
+        let freq_resolution = 1.0 / (frame_size as f64 * dt);
+        let hop_time = self.stft_hop_size as f64 * dt;
+        let frame_time =
+            |frame: f64| (frame * self.stft_hop_size as f64 + frame_size as f64 / 2.0) * dt;
+
+        let width = spectrogram.num_frames as f64 * hop_time;
+        let height = frame_size as f64 / 2.0 * freq_resolution;
+        egui_plot::Plot::new("Spectrogram Plot")
+            .height(
+                (ui.available_height() - diagram_height - ui.spacing().item_spacing.y).max(100.0),
+            )
+            .x_axis_label("Time")
+            .y_axis_label("Frequency (Hz)")
+            .show(ui, |plot_ui| {
+                if self.reassign_spectrogram {
+                    for (level, points) in spectrogram.reassigned.iter().enumerate() {
+                        plot_ui.points(
+                            Points::new(PlotPoints::from(points.clone()))
+                                .color(colormap((level + 1) as f32 / REASSIGNED_LEVELS as f32))
+                                .radius(1.5),
+                        );
+                    }
+                } else {
+                    plot_ui.image(PlotImage::new(
+                        &spectrogram.texture,
+                        PlotPoint::new(
+                            frame_time(0.0) - hop_time / 2.0 + width / 2.0,
+                            height / 2.0 - freq_resolution / 2.0,
+                        ),
+                        [width as f32, height as f32],
+                    ));
+                }
+                for (index, track) in spectrogram.tracks.iter().enumerate() {
+                    plot_ui.line(
+                        Line::new(
+                            track
+                                .iter()
+                                .map(|(time, frequency)| [*time, *frequency])
+                                .collect::<PlotPoints>(),
+                        )
+                        .color(egui::ecolor::Hsva::new(
+                            index as f32 * 0.618 % 1.0,
+                            0.6,
+                            1.0,
+                            1.0,
+                        ))
+                        .width(2.0),
+                    );
+                }
+            });
+
+        if combined_wave.len() < MAX_DIAGRAM_SAMPLES {
+            frame_overlap_diagram(ui, combined_wave.len(), frame_size, self.stft_hop_size);
+        } else {
+            ui.label(format!(
+                "Use fewer than {MAX_DIAGRAM_SAMPLES} samples to see the frame overlap diagram."
+            ));
+        }
+    }
+}
+
+/// The STFT of `combined_wave` with samples `dt` apart, or `None` if the wave is shorter than a
+/// frame.
+fn compute_spectrogram(
+    ctx: &egui::Context,
+    combined_wave: &[(f64, Complex<f64>)],
+    key: &SpectrogramKey,
+    dt: f64,
+) -> Option<Arc<Spectrogram>> {
+    let samples = combined_wave.iter().map(|(_, y)| y.re).collect::<Vec<_>>();
+    let frames = windowed_stft(&samples, key.frame_size, key.hop_size, key.window);
+    let frame_size = frames.first().map(Vec::len)?;
+    let freq_resolution = 1.0 / (frame_size as f64 * dt);
+
+    // Rows are frequency bins up to Nyquist and columns are frames.
+    let magnitudes = (0..frame_size / 2)
+        .map(|k| frames.iter().map(|frame| frame[k].norm()).collect())
+        .collect::<Vec<_>>();
+    let image = if key.decibels {
+        decibel_heatmap_image(&magnitudes, key.dynamic_range_db)
+    } else {
+        heatmap_image(&magnitudes)
+    };
+    let texture = ctx.load_texture("spectrogram", image, egui::TextureOptions::NEAREST);
+
+    let mut reassigned = vec![Vec::new(); REASSIGNED_LEVELS];
+    if key.reassign {
+        let [signal, time_deriv, freq_deriv] =
+            reassignment_stfts(&samples, key.frame_size, key.hop_size);
+        let points = spectral_reassignment(
+            &signal,
+            &time_deriv,
+            &freq_deriv,
+            freq_resolution,
+            key.hop_size,
+        );
+        let max = points.iter().map(|(_, _, m)| *m).fold(0.0, f64::max);
+        for (time, frequency, magnitude) in points {
+            let mut value = magnitude / max;
+            if key.decibels {
+                value = decibel_level(value, key.dynamic_range_db);
+            }
+            if value >= REASSIGNED_FLOOR {
+                let level =
+                    ((value * REASSIGNED_LEVELS as f64) as usize).min(REASSIGNED_LEVELS - 1);
+                reassigned[level].push([time * dt, frequency]);
+            }
+        }
+    }
+
+    let tracks = if key.track_peaks {
+        track_spectral_peaks(
+            &frames,
+            freq_resolution,
+            key.hop_size,
+            key.max_peak_gap_bins,
+        )
+    } else {
+        Vec::new()
+    };
+
+    Some(Arc::new(Spectrogram {
+        frame_size,
+        num_frames: frames.len(),
+        texture,
+        reassigned,
+        tracks,
+    }))
+}
+
+/// Draws the span of every STFT frame as a row, with the parts shared with neighbouring frames
+/// darkened. Long frames share more of their span, which is where the temporal smearing comes
+/// from.
+fn frame_overlap_diagram(ui: &mut egui::Ui, num_samples: usize, frame_size: usize, hop: usize) {
+    let starts = (0..=num_samples - frame_size)
+        .step_by(hop.max(1))
+        .collect::<Vec<_>>();
+    let size = egui::vec2(
+        ui.available_width(),
+        starts.len() as f32 * (DIAGRAM_ROW_HEIGHT + DIAGRAM_ROW_SPACING),
+    );
+    let (rect, _) = ui.allocate_exact_size(size, egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let sample_x = |n: usize| rect.left() + rect.width() * n as f32 / num_samples as f32;
+
+    for (index, &start) in starts.iter().enumerate() {
+        let top = rect.top() + index as f32 * (DIAGRAM_ROW_HEIGHT + DIAGRAM_ROW_SPACING);
+        let span = |from: usize, to: usize| {
+            egui::Rect::from_x_y_ranges(
+                sample_x(from)..=sample_x(to),
+                top..=top + DIAGRAM_ROW_HEIGHT,
+            )
+        };
+        let hue = index as f32 * 0.618 % 1.0;
+        painter.rect_filled(
+            span(start, start + frame_size),
+            1.0,
+            egui::ecolor::Hsva::new(hue, 0.5, 0.9, 1.0),
+        );
+
+        let neighbours = [index.checked_sub(1), Some(index + 1)];
+        for &other in neighbours.iter().flatten().filter_map(|i| starts.get(*i)) {
+            let from = start.max(other);
+            let to = (start + frame_size).min(other + frame_size);
+            if from < to {
+                painter.rect_filled(
+                    span(from, to),
+                    1.0,
+                    egui::ecolor::Hsva::new(hue, 0.7, 0.5, 1.0),
+                );
+            }
+        }
+    }
+}
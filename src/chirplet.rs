@@ -0,0 +1,59 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+/// Correlates the signal with Gaussian-windowed linear FM atoms `g(t) * exp(j*(ω₀t + α*t²/2))`.
+///
+/// Frequencies are in radians per sample and chirp rates in radians per sample². The signal is
+/// split into frames of `window_size` samples with 50% overlap. The result is indexed as
+/// `[freq_index * chirp_rates.len() + rate_index][frame]`.
+pub fn chirplet_transform(
+    signal: &[f64],
+    center_freqs: &[f64],
+    chirp_rates: &[f64],
+    window_size: usize,
+) -> Vec<Vec<Complex<f64>>> {
+    let window_size = window_size.max(1);
+    let hop = (window_size / 2).max(1);
+    let frame_starts = (0..signal.len().saturating_sub(window_size) + 1)
+        .step_by(hop)
+        .collect::<Vec<_>>();
+
+    let center = (window_size as f64 - 1.0) / 2.0;
+    let sigma = window_size as f64 / 6.0;
+    let window = (0..window_size)
+        .map(|n| {
+            let t = n as f64 - center;
+            (-0.5 * (t / sigma).powi(2)).exp() / (sigma * (2.0 * PI).sqrt())
+        })
+        .collect::<Vec<_>>();
+
+    let mut result = Vec::with_capacity(center_freqs.len() * chirp_rates.len());
+    for &center_freq in center_freqs {
+        for &chirp_rate in chirp_rates {
+            let atom = window
+                .iter()
+                .enumerate()
+                .map(|(n, g)| {
+                    let t = n as f64 - center;
+                    Complex::from_polar(*g, -(center_freq * t + chirp_rate * t * t / 2.0))
+                })
+                .collect::<Vec<_>>();
+
+            result.push(
+                frame_starts
+                    .iter()
+                    .map(|&start| {
+                        signal[start..]
+                            .iter()
+                            .zip(atom.iter())
+                            .map(|(x, a)| a * x)
+                            .sum()
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    result
+}
@@ -0,0 +1,136 @@
+use std::fmt::Write as _;
+
+use crate::app::{InputData, PeriodicFunction};
+
+/// Generates an `egui_plot` callback that draws the sum of the given functions.
+pub fn generate_egui_plot_code(functions: &[InputData]) -> String {
+    let mut body = String::new();
+    for function in functions {
+        if !body.is_empty() {
+            body.push_str(" + ");
+        }
+        let phase = format!(
+            "(std::f64::consts::TAU * x * {} + {})",
+            literal(function.frequency.0),
+            literal(function.phase.0)
+        );
+        let _ = write!(
+            body,
+            "{} * {} + {}",
+            literal(function.amplitude.0),
+            waveform_expression(&function.function, &phase),
+            literal(function.y_shift.0)
+        );
+    }
+    if body.is_empty() {
+        body.push_str("0.0 * x");
+    }
+
+    format!(
+        "PlotPoints::from_explicit_callback(|x: f64| {body}, f64::NEG_INFINITY..=f64::INFINITY, 10000)"
+    )
+}
+
+/// Rust expression for the unscaled waveform evaluated at the `phase` expression.
+fn waveform_expression(function: &PeriodicFunction, phase: &str) -> String {
+    match function {
+        PeriodicFunction::Sin => format!("{phase}.sin()"),
+        PeriodicFunction::Cos => format!("{phase}.cos()"),
+        PeriodicFunction::Square => format!(
+            "(if {phase}.rem_euclid(std::f64::consts::TAU) < std::f64::consts::PI {{ 1.0 }} else {{ -1.0 }})"
+        ),
+        PeriodicFunction::Triangle => {
+            format!("(std::f64::consts::FRAC_2_PI * {phase}.sin().asin())")
+        }
+        PeriodicFunction::Pulse(duty_cycle) => format!(
+            "(if ({phase} / std::f64::consts::TAU).rem_euclid(1.0) < {} {{ 1.0 }} else {{ -1.0 }})",
+            literal(duty_cycle.0)
+        ),
+        PeriodicFunction::Custom(expression) => expression.to_rust("x", phase),
+        PeriodicFunction::Sawtooth => format!(
+            "(1.0 - 2.0 * ((std::f64::consts::PI - {phase}) / std::f64::consts::TAU).rem_euclid(1.0))"
+        ),
+    }
+}
+
+/// Formats the value as a Rust float literal.
+fn literal(value: f64) -> String {
+    if value.is_nan() {
+        "f64::NAN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0.0 {
+            "f64::INFINITY"
+        } else {
+            "f64::NEG_INFINITY"
+        }
+        .to_owned()
+    } else if value < 0.0 {
+        format!("({value:?})")
+    } else {
+        format!("{value:?}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::Expression;
+
+    #[test]
+    fn generated_egui_plot_code_compiles() {
+        let functions = vec![
+            InputData::tone(PeriodicFunction::Sin, 1.0, 3.0),
+            InputData {
+                y_shift: (-0.5).into(),
+                phase: 1.25.into(),
+                ..InputData::tone(PeriodicFunction::Cos, 2.0, -1.5)
+            },
+            InputData::tone(PeriodicFunction::Square, 0.5, 2.0),
+            InputData::tone(PeriodicFunction::Triangle, 0.5, 4.0),
+            InputData::tone(PeriodicFunction::Sawtooth, 0.5, 6.0),
+            InputData::tone(PeriodicFunction::Pulse(0.1.into()), 0.5, 8.0),
+            InputData::tone(
+                PeriodicFunction::Custom(Expression::new("-2^abs(p) / e + sin(pi * p) * exp(-t)")),
+                0.5,
+                1.0,
+            ),
+        ];
+        let code = generate_egui_plot_code(&functions);
+
+        // A stand-in for egui_plot's PlotPoints, so the snippet can be compiled without the crate.
+        let source = format!(
+            "pub struct PlotPoints;\n\
+             impl PlotPoints {{\n\
+                 pub fn from_explicit_callback(\n\
+                     _function: impl Fn(f64) -> f64 + 'static,\n\
+                     _x_range: std::ops::RangeInclusive<f64>,\n\
+                     _points: usize,\n\
+                 ) -> Self {{\n\
+                     Self\n\
+                 }}\n\
+             }}\n\
+             pub fn points() -> PlotPoints {{\n\
+                 {code}\n\
+             }}\n"
+        );
+
+        let dir = std::env::temp_dir().join(format!("egui_plot_code_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("generated.rs");
+        std::fs::write(&file, source).unwrap();
+
+        let output =
+            std::process::Command::new(std::env::var("RUSTC").unwrap_or("rustc".to_owned()))
+                .args(["--edition=2021", "--crate-type=lib", "--out-dir"])
+                .arg(&dir)
+                .arg(&file)
+                .output()
+                .unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(
+            output.status.success(),
+            "{code}\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
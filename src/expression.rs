@@ -0,0 +1,429 @@
+use std::{fmt, hash};
+
+/// A formula in the time `t` and the phase `p` like `sin(p) * exp(-t)`, parsed once when it is
+/// set.
+///
+/// Supports `+ - * / ^`, parentheses, `sin`, `cos`, `exp`, `abs` and the constants `pi` and `e`.
+/// Two expressions are equal if their text is.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct Expression {
+    source: String,
+    parsed: Result<Node, String>,
+}
+
+#[derive(Clone, Debug)]
+enum Node {
+    Number(f64),
+    T,
+    Phase,
+    Negate(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Call(Builtin, Box<Node>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Builtin {
+    Sin,
+    Cos,
+    Exp,
+    Abs,
+}
+
+impl Expression {
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let parsed = Parser::new(&source).parse();
+        Self { source, parsed }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Why the text couldn't be parsed, if it couldn't.
+    pub fn error(&self) -> Option<&str> {
+        self.parsed.as_ref().err().map(String::as_str)
+    }
+
+    /// The value at time `t` and phase `p`, or 0 if the expression is invalid.
+    pub fn eval(&self, t: f64, p: f64) -> f64 {
+        self.parsed.as_ref().map_or(0.0, |node| node.eval(t, p))
+    }
+
+    /// Equivalent Rust expression with `t` and `p` replaced by the given expressions.
+    pub(crate) fn to_rust(&self, t: &str, p: &str) -> String {
+        match &self.parsed {
+            Ok(node) => node.to_rust(t, p),
+            Err(_) => "0.0".to_owned(),
+        }
+    }
+
+    /// The same formula with every use of the variable `from` replaced by `to`.
+    pub(crate) fn renamed(&self, from: &str, to: &str) -> Self {
+        let mut source = String::with_capacity(self.source.len());
+        let mut rest = self.source.as_str();
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+            source.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..len];
+            source.push_str(if name == from { to } else { name });
+            rest = &rest[len..];
+        }
+        source.push_str(rest);
+        Self::new(source)
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Expression {}
+
+impl hash::Hash for Expression {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+impl From<String> for Expression {
+    fn from(source: String) -> Self {
+        Self::new(source)
+    }
+}
+
+impl From<Expression> for String {
+    fn from(expression: Expression) -> Self {
+        expression.source
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Node {
+    fn eval(&self, t: f64, p: f64) -> f64 {
+        match self {
+            Node::Number(value) => *value,
+            Node::T => t,
+            Node::Phase => p,
+            Node::Negate(node) => -node.eval(t, p),
+            Node::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (lhs.eval(t, p), rhs.eval(t, p));
+                match op {
+                    BinaryOp::Add => lhs + rhs,
+                    BinaryOp::Subtract => lhs - rhs,
+                    BinaryOp::Multiply => lhs * rhs,
+                    BinaryOp::Divide => lhs / rhs,
+                    BinaryOp::Power => lhs.powf(rhs),
+                }
+            }
+            Node::Call(function, argument) => {
+                let argument = argument.eval(t, p);
+                match function {
+                    Builtin::Sin => argument.sin(),
+                    Builtin::Cos => argument.cos(),
+                    Builtin::Exp => argument.exp(),
+                    Builtin::Abs => argument.abs(),
+                }
+            }
+        }
+    }
+
+    /// Every subexpression is parenthesized so precedence never has to be reconstructed.
+    fn to_rust(&self, t: &str, p: &str) -> String {
+        match self {
+            // Typed, so methods on constants like `(2.0_f64).powf(t)` resolve.
+            Node::Number(value) => format!("{value:?}_f64"),
+            Node::T => t.to_owned(),
+            Node::Phase => p.to_owned(),
+            Node::Negate(node) => format!("(-{})", node.to_rust(t, p)),
+            Node::Binary(BinaryOp::Power, lhs, rhs) => {
+                format!("({}).powf({})", lhs.to_rust(t, p), rhs.to_rust(t, p))
+            }
+            Node::Binary(op, lhs, rhs) => {
+                let op = match op {
+                    BinaryOp::Add => "+",
+                    BinaryOp::Subtract => "-",
+                    BinaryOp::Multiply => "*",
+                    BinaryOp::Divide => "/",
+                    BinaryOp::Power => unreachable!(),
+                };
+                format!("({} {op} {})", lhs.to_rust(t, p), rhs.to_rust(t, p))
+            }
+            Node::Call(function, argument) => {
+                let method = match function {
+                    Builtin::Sin => "sin",
+                    Builtin::Cos => "cos",
+                    Builtin::Exp => "exp",
+                    Builtin::Abs => "abs",
+                };
+                format!("({}).{method}()", argument.to_rust(t, p))
+            }
+        }
+    }
+}
+
+/// Recursive descent over the grammar
+///
+/// ```text
+/// sum     = product (("+" | "-") product)*
+/// product = unary (("*" | "/") unary)*
+/// unary   = "-" unary | power
+/// power   = atom ("^" unary)?
+/// atom    = number | name | name "(" sum ")" | "(" sum ")"
+/// ```
+///
+/// so `^` binds tighter than negation and is right associative, like in most calculators.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    /// How many `unary` calls are active, every nesting goes through one.
+    depth: usize,
+}
+
+/// Deeper nesting is rejected instead of overflowing the stack.
+const MAX_DEPTH: usize = 100;
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Node, String> {
+        if self.peek().is_none() {
+            return Err("The expression is empty".to_owned());
+        }
+        let node = self.sum()?;
+        match self.peek() {
+            None => Ok(node),
+            Some(c) => Err(self.unexpected(c)),
+        }
+    }
+
+    /// The next character that isn't whitespace.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+        self.text[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, c: char) -> String {
+        format!("Unexpected '{c}' at column {}", self.position + 1)
+    }
+
+    fn sum(&mut self) -> Result<Node, String> {
+        let mut node = self.product()?;
+        loop {
+            let op = if self.eat('+') {
+                BinaryOp::Add
+            } else if self.eat('-') {
+                BinaryOp::Subtract
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.product()?));
+        }
+    }
+
+    fn product(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            let op = if self.eat('*') {
+                BinaryOp::Multiply
+            } else if self.eat('/') {
+                BinaryOp::Divide
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.depth == MAX_DEPTH {
+            return Err("Expression is nested too deeply".to_owned());
+        }
+        self.depth += 1;
+        let node = if self.eat('-') {
+            self.unary().map(|node| Node::Negate(Box::new(node)))
+        } else {
+            self.power()
+        };
+        self.depth -= 1;
+        node
+    }
+
+    fn power(&mut self) -> Result<Node, String> {
+        let base = self.atom()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            Ok(Node::Binary(
+                BinaryOp::Power,
+                Box::new(base),
+                Box::new(exponent),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            None => Err("The expression ends too early".to_owned()),
+            Some('(') => {
+                self.position += 1;
+                let node = self.sum()?;
+                if self.eat(')') {
+                    Ok(node)
+                } else {
+                    Err(self.expected_closing())
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if c.is_ascii_alphabetic() => self.name(),
+            Some(c) => Err(self.unexpected(c)),
+        }
+    }
+
+    fn expected_closing(&mut self) -> String {
+        match self.peek() {
+            None => "Missing ')' at the end".to_owned(),
+            Some(c) => format!(
+                "Expected ')' instead of '{c}' at column {}",
+                self.position + 1
+            ),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        let rest = &self.text[start..];
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.position += len;
+        &self.text[start..start + len]
+    }
+
+    fn number(&mut self) -> Result<Node, String> {
+        let column = self.position + 1;
+        let digits = self.take_while(|c| c.is_ascii_digit() || c == '.');
+        digits
+            .parse()
+            .map(Node::Number)
+            .map_err(|_| format!("'{digits}' at column {column} is not a number"))
+    }
+
+    fn name(&mut self) -> Result<Node, String> {
+        let column = self.position + 1;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        let function = match name {
+            "t" => return Ok(Node::T),
+            "p" => return Ok(Node::Phase),
+            "pi" => return Ok(Node::Number(std::f64::consts::PI)),
+            "e" => return Ok(Node::Number(std::f64::consts::E)),
+            "sin" => Builtin::Sin,
+            "cos" => Builtin::Cos,
+            "exp" => Builtin::Exp,
+            "abs" => Builtin::Abs,
+            _ => return Err(format!("Unknown name '{name}' at column {column}")),
+        };
+        if !self.eat('(') {
+            return Err(format!("Expected '(' after '{name}' at column {column}"));
+        }
+        let argument = self.sum()?;
+        if self.eat(')') {
+            Ok(Node::Call(function, Box::new(argument)))
+        } else {
+            Err(self.expected_closing())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    use crate::app::{get_combined_wave, InputData, PeriodicFunction};
+
+    const NUM_SAMPLES: usize = 1024;
+
+    #[test]
+    fn custom_expression() {
+        let expression = Expression::new("sin(2*p) * exp(-t) + 2^3^2 / -4 - abs(-pi)");
+        assert_eq!(expression.error(), None);
+        let (t, p) = (0.7_f64, 1.9_f64);
+        let expected = (2.0 * p).sin() * (-t).exp() + 2f64.powi(9) / -4.0 - std::f64::consts::PI;
+        assert!((expression.eval(t, p) - expected).abs() < 1e-12);
+
+        for invalid in ["", "sin t", "2 * (t + 1", "t +", "foo(t)", "1.2.3", "t $ 2"] {
+            assert!(Expression::new(invalid).error().is_some(), "{invalid:?}");
+        }
+        for nested in [
+            format!("{}t{}", "(".repeat(10_000), ")".repeat(10_000)),
+            format!("{}t", "-".repeat(10_000)),
+            format!("t{}", "^t".repeat(10_000)),
+        ] {
+            assert_eq!(
+                Expression::new(nested).error(),
+                Some("Expression is nested too deeply")
+            );
+        }
+
+        // A custom row samples like the equivalent built-in function.
+        let custom = InputData::tone(
+            PeriodicFunction::Custom(Expression::new("sin(p)")),
+            1.0,
+            5.0,
+        );
+        let builtin = InputData::tone(PeriodicFunction::Sin, 1.0, 5.0);
+        let custom = get_combined_wave(vec![custom], NUM_SAMPLES, TAU);
+        let builtin = get_combined_wave(vec![builtin], NUM_SAMPLES, TAU);
+        assert_eq!(custom, builtin);
+
+        // `t` is the time, so an envelope decays per second whatever the frequency.
+        let envelope = InputData::tone(
+            PeriodicFunction::Custom(Expression::new("exp(-t)")),
+            1.0,
+            5.0,
+        );
+        assert!((envelope.value_at(2.0) - (-2.0f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn renaming_a_variable_keeps_other_names() {
+        let renamed = Expression::new("sin(t)*exp(-t) + t2 + pi").renamed("t", "p");
+        assert_eq!(renamed.source(), "sin(p)*exp(-p) + t2 + pi");
+    }
+}
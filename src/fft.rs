@@ -0,0 +1,117 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+/// Discrete Fourier transform of any length. Powers of two use radix-2 Cooley-Tukey directly,
+/// other lengths go through Bluestein's algorithm.
+pub fn fft(input: &mut [Complex<f64>]) {
+    if input.len().is_power_of_two() {
+        radix2(input);
+    } else if input.len() > 1 {
+        bluestein(input);
+    }
+}
+
+/// In-place iterative Cooley-Tukey for power of two lengths.
+fn radix2(input: &mut [Complex<f64>]) {
+    let n = input.len();
+    if n <= 1 {
+        return;
+    }
+
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            input.swap(i, j);
+        }
+    }
+
+    // Twiddle factors of the last stage, the earlier stages use every (n / len)-th of them.
+    let twiddles = (0..n / 2)
+        .map(|k| Complex::from_polar(1.0, -2.0 * PI * k as f64 / n as f64))
+        .collect::<Vec<_>>();
+    let mut len = 2;
+    while len <= n {
+        let stride = n / len;
+        for chunk in input.chunks_exact_mut(len) {
+            let (even, odd) = chunk.split_at_mut(len / 2);
+            for (k, (e, o)) in even.iter_mut().zip(odd.iter_mut()).enumerate() {
+                let t = twiddles[k * stride] * *o;
+                *o = *e - t;
+                *e += t;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Rewrites the transform as a convolution with the chirp `e^(-iπk²/n)`, which is done with
+/// power of two transforms of at least `2n - 1` points.
+fn bluestein(input: &mut [Complex<f64>]) {
+    let n = input.len();
+    let m = (2 * n - 1).next_power_of_two();
+    // k² is reduced modulo 2n first so the angle stays accurate for large k.
+    let chirp = (0..n)
+        .map(|k| Complex::from_polar(1.0, -PI * ((k * k) % (2 * n)) as f64 / n as f64))
+        .collect::<Vec<_>>();
+
+    let mut a = vec![Complex::from(0.0); m];
+    for (a, (x, c)) in a.iter_mut().zip(input.iter().zip(&chirp)) {
+        *a = x * c;
+    }
+    let mut b = vec![Complex::from(0.0); m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+
+    radix2(&mut a);
+    radix2(&mut b);
+    a.iter_mut().zip(&b).for_each(|(a, b)| *a = (*a * b).conj());
+    radix2(&mut a);
+
+    for (x, (a, c)) in input.iter_mut().zip(a.iter().zip(&chirp)) {
+        *x = a.conj() / m as f64 * c;
+    }
+}
+
+/// Inverse of [`fft`], including the `1/n` normalization.
+pub fn ifft(input: &mut [Complex<f64>]) {
+    let n = input.len() as f64;
+    input.iter_mut().for_each(|x| *x = x.conj());
+    fft(input);
+    input.iter_mut().for_each(|x| *x = x.conj() / n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{get_combined_wave, InputData, PeriodicFunction};
+
+    #[test]
+    fn fft_of_non_power_of_two_length() {
+        // 1000 samples over 1 s put a 7 Hz cosine exactly into bin 7.
+        let wave = get_combined_wave(
+            vec![InputData::tone(PeriodicFunction::Cos, 1.0, 7.0)],
+            1000,
+            1.0,
+        );
+        let mut spectrum = wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+
+        assert_eq!(spectrum.len(), 1000);
+        let peak = (0..500)
+            .max_by(|a, b| spectrum[*a].norm().total_cmp(&spectrum[*b].norm()))
+            .unwrap();
+        assert_eq!(peak, 7);
+        assert!((spectrum[7].re - 500.0).abs() < 1e-6);
+        assert!((spectrum[993].re - 500.0).abs() < 1e-6);
+        for (k, bin) in spectrum.iter().enumerate() {
+            if k != 7 && k != 993 {
+                assert!(bin.norm() < 1e-6, "bin {k} is {bin}");
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use core::fmt;
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+use crate::{
+    fft::{fft, ifft},
+    window::WindowFunction,
+};
+
+/// Ideal frequency responses [`design_fir_window`] can approximate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FirType {
+    #[default]
+    LowPass,
+    HighPass,
+    BandPass,
+    Notch,
+}
+
+impl FirType {
+    pub const ALL: [FirType; 4] = [
+        FirType::LowPass,
+        FirType::HighPass,
+        FirType::BandPass,
+        FirType::Notch,
+    ];
+
+    /// How many cutoff frequencies the filter type takes.
+    pub fn num_cutoffs(&self) -> usize {
+        match self {
+            FirType::LowPass | FirType::HighPass => 1,
+            FirType::BandPass | FirType::Notch => 2,
+        }
+    }
+}
+
+impl fmt::Display for FirType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FirType::LowPass => write!(f, "Low-pass"),
+            FirType::HighPass => write!(f, "High-pass"),
+            FirType::BandPass => write!(f, "Band-pass"),
+            FirType::Notch => write!(f, "Notch"),
+        }
+    }
+}
+
+/// Designs a linear phase FIR filter by windowing the ideal sinc impulse response.
+///
+/// Returns `order + 1` taps. High-pass and notch filters need a tap in the middle, so odd orders
+/// are rounded up for them. Band-pass and notch filters use the first two `cutoffs` in Hz,
+/// the others only the first.
+pub fn design_fir_window(
+    filter_type: FirType,
+    cutoffs: &[f64],
+    order: usize,
+    window: WindowFunction,
+    sample_rate: f64,
+) -> Vec<f64> {
+    let order = match filter_type {
+        FirType::HighPass | FirType::Notch => order + order % 2,
+        FirType::LowPass | FirType::BandPass => order,
+    };
+    let center = order as f64 / 2.0;
+    let cutoff = |i: usize| cutoffs.get(i).copied().unwrap_or(0.0) / sample_rate;
+    // Impulse response of the ideal low-pass with normalized cutoff `fc`.
+    let low_pass = |fc: f64, n: f64| {
+        let x = n - center;
+        if x == 0.0 {
+            2.0 * fc
+        } else {
+            (2.0 * PI * fc * x).sin() / (PI * x)
+        }
+    };
+    let impulse = |n: f64| if n == center { 1.0 } else { 0.0 };
+
+    window
+        .coefficients(order + 1)
+        .into_iter()
+        .enumerate()
+        .map(|(n, w)| {
+            let n = n as f64;
+            let ideal = match filter_type {
+                FirType::LowPass => low_pass(cutoff(0), n),
+                FirType::HighPass => impulse(n) - low_pass(cutoff(0), n),
+                FirType::BandPass => low_pass(cutoff(1), n) - low_pass(cutoff(0), n),
+                FirType::Notch => impulse(n) - low_pass(cutoff(1), n) + low_pass(cutoff(0), n),
+            };
+            ideal * w
+        })
+        .collect()
+}
+
+/// Frequency response of the FIR filter `taps` at `frequency` Hz.
+pub fn fir_response(taps: &[f64], frequency: f64, sample_rate: f64) -> Complex<f64> {
+    let omega = 2.0 * PI * frequency / sample_rate;
+    taps.iter()
+        .enumerate()
+        .map(|(n, tap)| tap * Complex::from_polar(1.0, -omega * n as f64))
+        .sum()
+}
+
+/// Filters `signal` with `taps` by FFT convolution of consecutive blocks, adding up the
+/// overlapping tails. The output has the length of the signal.
+pub fn overlap_add(signal: &[f64], taps: &[f64]) -> Vec<f64> {
+    let mut output = vec![0.0; signal.len()];
+    if taps.is_empty() {
+        return output;
+    }
+
+    let block_size = taps.len().next_power_of_two();
+    let fft_size = (block_size + taps.len() - 1).next_power_of_two();
+    let mut kernel = taps
+        .iter()
+        .map(|tap| Complex::from(*tap))
+        .chain(std::iter::repeat(Complex::from(0.0)))
+        .take(fft_size)
+        .collect::<Vec<_>>();
+    fft(&mut kernel);
+
+    for (index, block) in signal.chunks(block_size).enumerate() {
+        let mut buffer = block
+            .iter()
+            .map(|x| Complex::from(*x))
+            .chain(std::iter::repeat(Complex::from(0.0)))
+            .take(fft_size)
+            .collect::<Vec<_>>();
+        fft(&mut buffer);
+        buffer.iter_mut().zip(&kernel).for_each(|(x, h)| *x *= h);
+        ifft(&mut buffer);
+
+        let start = index * block_size;
+        for (y, x) in output[start..].iter_mut().zip(&buffer) {
+            *y += x.re;
+        }
+    }
+    output
+}
+
+/// Formats the recursive filter `a[0]*y[n] = Σ b[i]*x[n-i] - Σ a[i]*y[n-i]` as a difference
+/// equation, normalized so `a[0]` is one. Zero coefficients are left out.
+pub fn filter_to_difference_equation(b: &[f64], a: &[f64]) -> String {
+    let a0 = a.first().copied().filter(|a0| *a0 != 0.0).unwrap_or(1.0);
+    let feedforward = b.iter().enumerate().map(|(i, b)| (b / a0, 'x', i));
+    let feedback = a.iter().enumerate().skip(1).map(|(i, a)| (-a / a0, 'y', i));
+
+    let mut equation = String::from("y[n] =");
+    let mut first = true;
+    for (coefficient, signal, delay) in feedforward.chain(feedback) {
+        if coefficient == 0.0 {
+            continue;
+        }
+
+        let sign = match (first, coefficient < 0.0) {
+            (true, false) => " ",
+            (true, true) => " -",
+            (false, false) => " + ",
+            (false, true) => " - ",
+        };
+        let index = if delay == 0 {
+            "n".to_owned()
+        } else {
+            format!("n-{delay}")
+        };
+        equation.push_str(&format!("{sign}{}*{signal}[{index}]", coefficient.abs()));
+        first = false;
+    }
+    if first {
+        equation.push_str(" 0");
+    }
+
+    equation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difference_equation_format() {
+        assert_eq!(
+            filter_to_difference_equation(&[0.5, 0.5], &[1.0]),
+            "y[n] = 0.5*x[n] + 0.5*x[n-1]"
+        );
+        assert_eq!(
+            filter_to_difference_equation(&[2.0, 0.0, -1.0], &[2.0, -0.5]),
+            "y[n] = 1*x[n] - 0.5*x[n-2] + 0.25*y[n-1]"
+        );
+    }
+
+    #[test]
+    fn windowed_fir_low_pass() {
+        let sample_rate = 1000.0;
+        let taps = design_fir_window(
+            FirType::LowPass,
+            &[100.0],
+            64,
+            WindowFunction::Hamming,
+            sample_rate,
+        );
+        assert_eq!(taps.len(), 65);
+        assert!((fir_response(&taps, 0.0, sample_rate).norm() - 1.0).abs() < 0.01);
+        assert!(fir_response(&taps, 300.0, sample_rate).norm() < 0.01);
+
+        // Overlap-add agrees with direct convolution.
+        let signal = (0..300).map(|n| (n as f64 * 0.3).sin()).collect::<Vec<_>>();
+        let filtered = overlap_add(&signal, &taps);
+        for (n, y) in filtered.iter().enumerate() {
+            let direct = (0..=n.min(taps.len() - 1))
+                .map(|i| taps[i] * signal[n - i])
+                .sum::<f64>();
+            assert!((y - direct).abs() < 1e-9);
+        }
+    }
+}
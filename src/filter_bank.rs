@@ -0,0 +1,64 @@
+use std::f64::consts::SQRT_2;
+
+/// Splits the signal into `num_bands` subbands with a tree of two-band Haar QMF stages, each
+/// band downsampled by `num_bands`.
+///
+/// `num_bands` is rounded up to a power of two and the signal is truncated to a multiple of it.
+/// The subbands are returned in order of increasing frequency.
+pub fn qmf_filter_bank(signal: &[f64], num_bands: usize) -> Vec<Vec<f64>> {
+    let num_bands = num_bands.max(1).next_power_of_two();
+    let length = signal.len() - signal.len() % num_bands;
+
+    let mut bands = vec![signal[..length].to_vec()];
+    while bands.len() < num_bands {
+        bands = bands
+            .iter()
+            .flat_map(|band| {
+                let (low, high): (Vec<f64>, Vec<f64>) = band
+                    .chunks_exact(2)
+                    .map(|pair| ((pair[0] + pair[1]) / SQRT_2, (pair[0] - pair[1]) / SQRT_2))
+                    .unzip();
+                [low, high]
+            })
+            .collect();
+    }
+
+    // Downsampling a highpass band mirrors its spectrum, so the tree leaves are in Gray code
+    // order of frequency.
+    let mut ordered = vec![Vec::new(); num_bands];
+    for (index, band) in bands.into_iter().enumerate() {
+        ordered[gray_decode(index)] = band;
+    }
+    ordered
+}
+
+/// Inverse of [`qmf_filter_bank`]: upsamples and sums the subbands back into one signal.
+pub fn qmf_synthesis(subbands: &[Vec<f64>]) -> Vec<f64> {
+    let mut bands = (0..subbands.len())
+        .map(|index| subbands[gray_decode(index)].clone())
+        .collect::<Vec<_>>();
+
+    while bands.len() > 1 {
+        bands = bands
+            .chunks_exact(2)
+            .map(|pair| {
+                pair[0]
+                    .iter()
+                    .zip(pair[1].iter())
+                    .flat_map(|(low, high)| [(low + high) / SQRT_2, (low - high) / SQRT_2])
+                    .collect()
+            })
+            .collect();
+    }
+
+    bands.pop().unwrap_or_default()
+}
+
+fn gray_decode(mut gray: usize) -> usize {
+    let mut binary = gray;
+    while gray > 0 {
+        gray >>= 1;
+        binary ^= gray;
+    }
+    binary
+}
@@ -0,0 +1,120 @@
+use std::ops::RangeInclusive;
+
+use num_complex::Complex;
+
+/// The gains in dB the control points and the curve are limited to.
+pub const GAIN_RANGE_DB: RangeInclusive<f64> = -120.0..=40.0;
+
+/// A natural cubic spline through `(x, gain_db)` control points, solved once so it can be
+/// evaluated at every bin.
+///
+/// Without control points the gain is 0 dB. Outside the control points the gain of the nearest
+/// end point is used. The spline can overshoot between control points, so its values are clamped
+/// to [`GAIN_RANGE_DB`].
+#[derive(Clone, Debug, Default)]
+pub struct GainCurve {
+    /// The control points sorted by x, without duplicate x coordinates.
+    points: Vec<(f64, f64)>,
+    second_derivatives: Vec<f64>,
+}
+
+impl GainCurve {
+    pub fn new(points: &[(f64, f64)]) -> Self {
+        let mut points = points.to_vec();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        points.dedup_by(|a, b| a.0 == b.0);
+        let second_derivatives = if points.len() > 2 {
+            second_derivatives(&points)
+        } else {
+            vec![0.0; points.len()]
+        };
+        Self {
+            points,
+            second_derivatives,
+        }
+    }
+
+    /// The gain in dB at `x`.
+    pub fn gain_db(&self, x: f64) -> f64 {
+        let points = &self.points;
+        match points.as_slice() {
+            [] => return 0.0,
+            [(_, gain)] => return *gain,
+            [(first_x, first_gain), .., (last_x, last_gain)] => {
+                if x <= *first_x {
+                    return *first_gain;
+                }
+                if x >= *last_x {
+                    return *last_gain;
+                }
+            }
+        }
+
+        let segment = points.partition_point(|(point_x, _)| *point_x < x).max(1) - 1;
+        let ((x0, y0), (x1, y1)) = (points[segment], points[segment + 1]);
+        let (m0, m1) = (
+            self.second_derivatives[segment],
+            self.second_derivatives[segment + 1],
+        );
+
+        let h = x1 - x0;
+        let a = (x1 - x) / h;
+        let b = (x - x0) / h;
+        let gain = a * y0 + b * y1 + ((a.powi(3) - a) * m0 + (b.powi(3) - b) * m1) * h * h / 6.0;
+        gain.clamp(*GAIN_RANGE_DB.start(), *GAIN_RANGE_DB.end())
+    }
+
+    /// Multiplies each bin by the linear gain of the curve.
+    ///
+    /// `bin_x` maps a bin index to the x coordinate the curve was drawn against. Bins in the
+    /// upper half use the coordinate of their mirror bin so real signals stay real.
+    pub fn apply(&self, spectrum: &mut [Complex<f64>], bin_x: impl Fn(usize) -> f64) {
+        let n = spectrum.len();
+        for (k, bin) in spectrum.iter_mut().enumerate() {
+            let gain_db = self.gain_db(bin_x(k.min(n - k)));
+            *bin *= 10f64.powf(gain_db / 20.0);
+        }
+    }
+}
+
+/// Solves the tridiagonal system for the spline's second derivatives at the control points.
+fn second_derivatives(points: &[(f64, f64)]) -> Vec<f64> {
+    let n = points.len();
+    let mut m = vec![0.0; n];
+    let mut c_prime = vec![0.0; n];
+    let mut d_prime = vec![0.0; n];
+
+    for i in 1..n - 1 {
+        let h0 = points[i].0 - points[i - 1].0;
+        let h1 = points[i + 1].0 - points[i].0;
+        let rhs =
+            6.0 * ((points[i + 1].1 - points[i].1) / h1 - (points[i].1 - points[i - 1].1) / h0);
+        let diagonal = 2.0 * (h0 + h1) - h0 * c_prime[i - 1];
+        c_prime[i] = h1 / diagonal;
+        d_prime[i] = (rhs - h0 * d_prime[i - 1]) / diagonal;
+    }
+    for i in (1..n - 1).rev() {
+        m[i] = d_prime[i] - c_prime[i] * m[i + 1];
+    }
+
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spline_goes_through_the_control_points_in_any_order() {
+        let points = [(30.0, -6.0), (10.0, 0.0), (20.0, 6.0), (20.0, 3.0)];
+        let curve = GainCurve::new(&points);
+        for (x, gain) in [(10.0, 0.0), (20.0, 6.0), (30.0, -6.0)] {
+            assert!((curve.gain_db(x) - gain).abs() < 1e-12, "{x}");
+        }
+        assert_eq!(curve.gain_db(0.0), 0.0);
+        assert_eq!(curve.gain_db(100.0), -6.0);
+        // Between the control points the spline is smooth rather than piecewise linear.
+        assert!(curve.gain_db(15.0) > 3.0);
+        assert_eq!(GainCurve::new(&[]).gain_db(5.0), 0.0);
+    }
+}
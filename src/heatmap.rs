@@ -0,0 +1,59 @@
+use egui::{Color32, ColorImage};
+
+/// Renders `values[row][column]` as an image, normalizing to the largest value.
+///
+/// The first row ends up at the bottom of the image so it lines up with a plot's y axis.
+pub fn heatmap_image(values: &[Vec<f64>]) -> ColorImage {
+    let max = largest(values);
+    image(values, |value| value / max)
+}
+
+/// Like [`heatmap_image`], but on a decibel scale that shows `dynamic_range_db` below the largest
+/// value. Anything quieter is drawn like zero.
+pub fn decibel_heatmap_image(values: &[Vec<f64>], dynamic_range_db: f64) -> ColorImage {
+    let max = largest(values);
+    image(values, |value| decibel_level(value / max, dynamic_range_db))
+}
+
+/// Maps a magnitude relative to the largest one from `-dynamic_range_db..=0` dB to `0.0..=1.0`.
+pub fn decibel_level(relative: f64, dynamic_range_db: f64) -> f64 {
+    if relative <= 0.0 {
+        return 0.0;
+    }
+    (1.0 + 20.0 * relative.log10() / dynamic_range_db.max(f64::MIN_POSITIVE)).clamp(0.0, 1.0)
+}
+
+fn largest(values: &[Vec<f64>]) -> f64 {
+    values
+        .iter()
+        .flatten()
+        .fold(f64::MIN_POSITIVE, |max, value| max.max(*value))
+}
+
+fn image(values: &[Vec<f64>], normalize: impl Fn(f64) -> f64) -> ColorImage {
+    let height = values.len();
+    let width = values.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in values.iter().rev() {
+        for column in 0..width {
+            let value = normalize(row.get(column).copied().unwrap_or(0.0));
+            pixels.push(colormap(value as f32));
+        }
+    }
+
+    ColorImage {
+        size: [width, height],
+        pixels,
+    }
+}
+
+/// Maps `0.0..=1.0` from dark blue over red to yellow.
+pub fn colormap(value: f32) -> Color32 {
+    let value = value.clamp(0.0, 1.0);
+    Color32::from_rgb(
+        (255.0 * (value * 2.0).min(1.0)) as u8,
+        (255.0 * (value * 2.0 - 1.0).max(0.0)) as u8,
+        (128.0 * (1.0 - value * 2.0).max(0.0)) as u8,
+    )
+}
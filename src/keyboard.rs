@@ -0,0 +1,81 @@
+use egui::{Color32, Rect, Sense, Stroke, Vec2};
+
+const WHITE_KEY_SIZE: Vec2 = Vec2::new(16.0, 60.0);
+const BLACK_KEY_SIZE: Vec2 = Vec2::new(10.0, 36.0);
+const NUM_OCTAVES: u8 = 2;
+
+/// A key on the two-octave keyboard from C4 to B5, as a MIDI note number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PianoKey(pub u8);
+
+impl PianoKey {
+    const C4: u8 = 60;
+
+    pub fn all() -> impl Iterator<Item = PianoKey> {
+        (Self::C4..Self::C4 + 12 * NUM_OCTAVES).map(PianoKey)
+    }
+
+    fn is_black(self) -> bool {
+        matches!(self.0 % 12, 1 | 3 | 6 | 8 | 10)
+    }
+
+    /// Rectangle of the key relative to the keyboard's top left corner.
+    fn rect(self, origin: egui::Pos2) -> Rect {
+        let offset = self.0 - Self::C4;
+        let octave = (offset / 12) as f32;
+        // Number of white keys to the left of this key within its octave.
+        let white_index = [0, 1, 1, 2, 2, 3, 4, 4, 5, 5, 6, 6][(offset % 12) as usize] as f32;
+        let white_left = origin.x + (octave * 7.0 + white_index) * WHITE_KEY_SIZE.x;
+
+        if self.is_black() {
+            Rect::from_min_size(
+                egui::pos2(white_left - BLACK_KEY_SIZE.x / 2.0, origin.y),
+                BLACK_KEY_SIZE,
+            )
+        } else {
+            Rect::from_min_size(egui::pos2(white_left, origin.y), WHITE_KEY_SIZE)
+        }
+    }
+}
+
+/// Equal temperament frequency of the key with A4 = 440 Hz.
+pub fn piano_key_to_freq(note: PianoKey) -> f64 {
+    440.0 * 2f64.powf((note.0 as f64 - 69.0) / 12.0)
+}
+
+pub fn render_mini_keyboard(ui: &mut egui::Ui, selected_note: &mut Option<PianoKey>) {
+    let size = Vec2::new(
+        WHITE_KEY_SIZE.x * 7.0 * NUM_OCTAVES as f32,
+        WHITE_KEY_SIZE.y,
+    );
+    let (rect, response) = ui.allocate_exact_size(size, Sense::click());
+    let painter = ui.painter_at(rect);
+    let highlight = ui.visuals().selection.bg_fill;
+
+    // White keys are drawn first so the black keys end up on top of them.
+    let (black_keys, white_keys): (Vec<_>, Vec<_>) = PianoKey::all().partition(|k| k.is_black());
+    for key in white_keys.iter().chain(black_keys.iter()) {
+        let fill = if *selected_note == Some(*key) {
+            highlight
+        } else if key.is_black() {
+            Color32::BLACK
+        } else {
+            Color32::WHITE
+        };
+        let key_rect = key.rect(rect.min);
+        painter.rect_filled(key_rect, 1.0, fill);
+        painter.rect_stroke(key_rect, 1.0, Stroke::new(1.0, Color32::GRAY));
+    }
+
+    if response.clicked() {
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let clicked = black_keys
+                .iter()
+                .chain(white_keys.iter())
+                .find(|key| key.rect(rect.min).contains(pointer));
+            if let Some(key) = clicked {
+                *selected_note = Some(*key);
+            }
+        }
+    }
+}
@@ -1,4 +1,22 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
+mod analysis;
 mod app;
+mod chirplet;
+mod codegen;
+mod expression;
+mod fft;
+mod filter;
+mod filter_bank;
+mod gain_curve;
+mod heatmap;
+mod keyboard;
+mod masking;
+mod signal_file;
+mod signal_flow;
+mod spectral_gate;
+mod spectrum;
+mod stft;
+mod weighting;
+mod window;
 pub use app::TemplateApp;
@@ -0,0 +1,42 @@
+/// Frequency in Bark after Zwicker and Terhardt.
+fn bark(frequency: f64) -> f64 {
+    let frequency = frequency.abs();
+    13.0 * (0.00076 * frequency).atan() + 3.5 * (frequency / 7500.0).powi(2).atan()
+}
+
+/// How far below the masker's level its masking curve peaks.
+const MASKING_OFFSET_DB: f64 = 10.0;
+/// Slope of the masking curve towards lower frequencies.
+const LOWER_SLOPE_DB_PER_BARK: f64 = 27.0;
+/// Slope of the masking curve towards higher frequencies, which mask less steeply.
+const UPPER_SLOPE_DB_PER_BARK: f64 = 10.0;
+
+/// Combined masking threshold in dB for each of `num_bins` bins.
+///
+/// Every `(frequency, level_db)` peak spreads an asymmetric triangle in the Bark domain, and the
+/// triangles are added up in the power domain.
+pub fn compute_masking_threshold(
+    peaks: &[(f64, f64)],
+    freq_resolution: f64,
+    num_bins: usize,
+) -> Vec<f64> {
+    (0..num_bins)
+        .map(|k| {
+            let z = bark(k as f64 * freq_resolution);
+            let power = peaks
+                .iter()
+                .map(|(frequency, level_db)| {
+                    let distance = z - bark(*frequency);
+                    let slope = if distance < 0.0 {
+                        LOWER_SLOPE_DB_PER_BARK
+                    } else {
+                        UPPER_SLOPE_DB_PER_BARK
+                    };
+                    let threshold_db = level_db - MASKING_OFFSET_DB - slope * distance.abs();
+                    10f64.powf(threshold_db / 10.0)
+                })
+                .sum::<f64>();
+            10.0 * power.log10()
+        })
+        .collect()
+}
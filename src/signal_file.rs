@@ -0,0 +1,229 @@
+/// Reads a single column of sample values, one per line.
+///
+/// Blank lines are skipped and a first line that isn't a number is taken as a header.
+pub fn parse_csv(text: &str) -> Result<Vec<f64>, String> {
+    let mut samples = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields = line.split([',', ';', '\t']).collect::<Vec<_>>();
+        if fields.len() != 1 {
+            return Err(format!(
+                "Line {} has {} columns, expected a single column",
+                index + 1,
+                fields.len()
+            ));
+        }
+        match fields[0].trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => samples.push(value),
+            Ok(_) => return Err(format!("Line {} is not a finite number", index + 1)),
+            Err(_) if samples.is_empty() && index == 0 => {}
+            Err(_) => return Err(format!("Line {} is not a number: {line}", index + 1)),
+        }
+    }
+
+    if samples.is_empty() {
+        return Err("The file contains no samples".to_owned());
+    }
+    Ok(samples)
+}
+
+/// Writes the samples as a single column with a header, which [`parse_csv`] reads back.
+pub fn samples_to_csv(samples: &[f64]) -> String {
+    let mut csv = String::from("sample\n");
+    for sample in samples {
+        csv.push_str(&format!("{sample:?}\n"));
+    }
+    csv
+}
+
+/// Reads a PCM (8, 16, 24 or 32 bit) or IEEE float (32 or 64 bit) WAV file.
+///
+/// Returns the samples scaled to `-1.0..=1.0` together with the sample rate. Files with more than
+/// one channel are mixed down to mono by averaging the channels of every frame.
+pub fn parse_wav(bytes: &[u8]) -> Result<(Vec<f64>, f64), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_owned());
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([
+            bytes[offset + 4],
+            bytes[offset + 5],
+            bytes[offset + 6],
+            bytes[offset + 7],
+        ]) as usize;
+        let body = &bytes[offset + 8..(offset + 8).saturating_add(size).min(bytes.len())];
+        match id {
+            b"fmt " if body.len() >= 16 => format = Some(body),
+            b"data" => data = Some(body),
+            _ => {}
+        }
+        // Chunks are padded to an even size.
+        offset = offset.saturating_add(8 + size + size % 2);
+    }
+
+    let format = format.ok_or("The WAV file has no format chunk")?;
+    let data = data.ok_or("The WAV file has no data chunk")?;
+    let read_u16 = |at: usize| u16::from_le_bytes([format[at], format[at + 1]]);
+    let audio_format = match read_u16(0) {
+        // WAVE_FORMAT_EXTENSIBLE keeps the actual format at the start of its sub-format GUID.
+        0xfffe if format.len() >= 26 => read_u16(24),
+        audio_format => audio_format,
+    };
+    let channels = read_u16(2);
+    let sample_rate = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+    let bits = read_u16(14);
+
+    if channels == 0 {
+        return Err("The WAV file has no channels".to_owned());
+    }
+    if sample_rate == 0 {
+        return Err("The WAV file has a sample rate of zero".to_owned());
+    }
+
+    let width = usize::from(bits / 8);
+    let decode: fn(&[u8]) -> f64 = match (audio_format, bits) {
+        (1, 8) => |b| (f64::from(b[0]) - 128.0) / 128.0,
+        (1, 16) => |b| f64::from(i16::from_le_bytes([b[0], b[1]])) / 32768.0,
+        (1, 24) => |b| f64::from(i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) / 8_388_608.0,
+        (1, 32) => |b| f64::from(i32::from_le_bytes([b[0], b[1], b[2], b[3]])) / 2_147_483_648.0,
+        (3, 32) => |b| f64::from(f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        (3, 64) => |b| f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]),
+        _ => {
+            return Err(format!(
+                "Unsupported WAV encoding (format {audio_format}, {bits} bits)"
+            ))
+        }
+    };
+
+    let channels = usize::from(channels);
+    let samples = data
+        .chunks_exact(width * channels)
+        .map(|frame| frame.chunks_exact(width).map(decode).sum::<f64>() / channels as f64)
+        .collect::<Vec<_>>();
+    if samples.is_empty() {
+        return Err("The file contains no samples".to_owned());
+    }
+    // Float files can hold NaN or infinity, which would poison every spectrum.
+    if let Some(index) = samples.iter().position(|sample| !sample.is_finite()) {
+        return Err(format!("Frame {} is not a finite number", index + 1));
+    }
+    Ok((samples, f64::from(sample_rate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_import() {
+        assert_eq!(parse_csv("value\n1.5\n\n-2\n"), Ok(vec![1.5, -2.0]));
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("1.0\n2.0,3.0\n").is_err());
+        assert!(parse_csv("1.0\nx\n").is_err());
+    }
+
+    #[test]
+    fn exported_csv_can_be_imported() {
+        let samples = [0.25, -1.0, 1.0 / 3.0, 1e-12];
+        assert_eq!(parse_csv(&samples_to_csv(&samples)).unwrap(), samples);
+    }
+
+    #[test]
+    fn wav_import() {
+        let samples: [i16; 4] = [0, 16384, -32768, 32767];
+        let data = samples
+            .iter()
+            .flat_map(|sample| sample.to_le_bytes())
+            .collect::<Vec<_>>();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, one channel, 8 kHz, 16 kB/s, 2 byte frames, 16 bits.
+        for field in [1u16, 1] {
+            wav.extend_from_slice(&field.to_le_bytes());
+        }
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        for field in [2u16, 16] {
+            wav.extend_from_slice(&field.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+
+        let (decoded, sample_rate) = parse_wav(&wav).unwrap();
+        assert_eq!(sample_rate, 8000.0);
+        assert_eq!(decoded, vec![0.0, 0.5, -1.0, 32767.0 / 32768.0]);
+        assert!(parse_wav(&wav[..40]).is_err());
+        assert!(parse_wav(b"not a wav file").is_err());
+
+        // The same frames as two channels, the second one silent, are averaged.
+        let stereo = samples
+            .iter()
+            .flat_map(|sample| [sample.to_le_bytes(), [0, 0]])
+            .flatten()
+            .collect::<Vec<_>>();
+        let mut wav = wav[..22].to_vec();
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&32000u32.to_le_bytes());
+        for field in [4u16, 16] {
+            wav.extend_from_slice(&field.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(stereo.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&stereo);
+        let (downmixed, _) = parse_wav(&wav).unwrap();
+        let halved = decoded
+            .iter()
+            .map(|sample| sample / 2.0)
+            .collect::<Vec<_>>();
+        assert_eq!(downmixed, halved);
+    }
+
+    #[test]
+    fn float_wav_with_non_finite_samples_is_rejected() {
+        let float_wav = |samples: &[f32]| {
+            let data = samples
+                .iter()
+                .flat_map(|sample| sample.to_le_bytes())
+                .collect::<Vec<_>>();
+            let mut wav = Vec::new();
+            wav.extend_from_slice(b"RIFF");
+            wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+            wav.extend_from_slice(b"WAVEfmt ");
+            wav.extend_from_slice(&16u32.to_le_bytes());
+            // IEEE float, one channel, 8 kHz, 32 kB/s, 4 byte frames, 32 bits.
+            for field in [3u16, 1] {
+                wav.extend_from_slice(&field.to_le_bytes());
+            }
+            wav.extend_from_slice(&8000u32.to_le_bytes());
+            wav.extend_from_slice(&32000u32.to_le_bytes());
+            for field in [4u16, 32] {
+                wav.extend_from_slice(&field.to_le_bytes());
+            }
+            wav.extend_from_slice(b"data");
+            wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            wav.extend_from_slice(&data);
+            parse_wav(&wav)
+        };
+
+        assert_eq!(float_wav(&[0.5, -0.25]), Ok((vec![0.5, -0.25], 8000.0)));
+        assert_eq!(
+            float_wav(&[0.5, f32::NAN]),
+            Err("Frame 2 is not a finite number".to_owned())
+        );
+        assert!(float_wav(&[f32::INFINITY]).is_err());
+        assert!(float_wav(&[f32::NEG_INFINITY, 0.0]).is_err());
+    }
+}
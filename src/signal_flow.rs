@@ -0,0 +1,45 @@
+use egui::{Color32, ProgressBar};
+
+/// Peak levels recorded at each stage of the processing pipeline while it runs.
+#[derive(Clone, Default)]
+pub struct SignalFlow {
+    stages: Vec<(&'static str, Option<f64>)>,
+}
+
+impl SignalFlow {
+    /// Records the peak absolute value of `samples` for the given stage.
+    pub fn record(&mut self, stage: &'static str, samples: impl IntoIterator<Item = f64>) {
+        let peak = samples
+            .into_iter()
+            .fold(0.0, |peak: f64, x| peak.max(x.abs()));
+        self.stages.push((stage, Some(peak)));
+    }
+
+    /// Records a stage that is currently not part of the pipeline.
+    pub fn bypass(&mut self, stage: &'static str) {
+        self.stages.push((stage, None));
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui, clip_threshold: f64) {
+        egui::Grid::new("signal_flow_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (index, (stage, peak)) in self.stages.iter().enumerate() {
+                    ui.label(format!("{}. {stage}", index + 1));
+                    match peak {
+                        Some(peak) => {
+                            let clipped = *peak > clip_threshold;
+                            let bar = ProgressBar::new((peak / clip_threshold) as f32)
+                                .desired_width(200.0)
+                                .text(format!("{peak:.3}"));
+                            ui.add(if clipped { bar.fill(Color32::RED) } else { bar });
+                        }
+                        None => {
+                            ui.weak("bypassed");
+                        }
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -0,0 +1,29 @@
+use num_complex::Complex;
+
+/// How far above the threshold, as a multiple of it, the soft gate starts to attenuate.
+const SOFT_GATE_KNEE: f64 = 2.0;
+
+/// Zeroes every bin whose magnitude is below `threshold_linear` times the largest magnitude.
+pub fn apply_spectral_gate(bins: &mut [Complex<f64>], threshold_linear: f64) {
+    let threshold = threshold_linear * max_magnitude(bins);
+    for bin in bins.iter_mut().filter(|bin| bin.norm() < threshold) {
+        *bin = Complex::ZERO;
+    }
+}
+
+/// Like [`apply_spectral_gate`], but bins between the threshold and [`SOFT_GATE_KNEE`] times it
+/// are faded out with a smoothstep gain instead of being cut off.
+pub fn apply_soft_spectral_gate(bins: &mut [Complex<f64>], threshold_linear: f64) {
+    let threshold = threshold_linear * max_magnitude(bins);
+    if threshold <= 0.0 {
+        return;
+    }
+    for bin in bins.iter_mut() {
+        let t = ((bin.norm() / threshold - 1.0) / (SOFT_GATE_KNEE - 1.0)).clamp(0.0, 1.0);
+        *bin *= t * t * (3.0 - 2.0 * t);
+    }
+}
+
+fn max_magnitude(bins: &[Complex<f64>]) -> f64 {
+    bins.iter().map(|bin| bin.norm()).fold(0.0, f64::max)
+}
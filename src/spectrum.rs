@@ -0,0 +1,149 @@
+use num_complex::Complex;
+
+/// Spacing of the FFT bins in Hz, the sample rate divided by the number of samples.
+pub fn frequency_resolution(input_signal_range: f64) -> f64 {
+    1.0 / input_signal_range
+}
+
+/// Frequency in Hz of bin `k` of an FFT of `num_samples` samples over `input_signal_range`.
+///
+/// Bins above `num_samples / 2` hold the negative frequencies `k - num_samples`.
+pub fn bin_to_frequency(k: usize, num_samples: usize, input_signal_range: f64) -> f64 {
+    let k = if k > num_samples / 2 {
+        k as f64 - num_samples as f64
+    } else {
+        k as f64
+    };
+    k * frequency_resolution(input_signal_range)
+}
+
+/// The bins below Nyquist. The rest of a real signal's spectrum is their mirror image.
+pub fn one_sided(spectrum: &[Complex<f64>]) -> &[Complex<f64>] {
+    &spectrum[..spectrum.len() / 2]
+}
+
+/// Zeroes every bin whose frequency lies outside `low..=high` Hz. Negative frequencies are
+/// compared by their magnitude, so a real signal stays real.
+pub fn apply_band_mask(spectrum: &mut [Complex<f64>], input_signal_range: f64, band: (f64, f64)) {
+    let (low, high) = (band.0.min(band.1), band.0.max(band.1));
+    let num_samples = spectrum.len();
+    for (k, bin) in spectrum.iter_mut().enumerate() {
+        let frequency = bin_to_frequency(k, num_samples, input_signal_range).abs();
+        if frequency < low || frequency > high {
+            *bin = Complex::from(0.0);
+        }
+    }
+}
+
+/// The `count` strongest terms of the Fourier series `z(t) = Σ c e^(2πi f t)` whose real part
+/// passes through the samples the unwindowed `spectrum` was computed from, as `(f in Hz, c)`.
+///
+/// Only frequencies up to Nyquist are used, with the negative ones folded onto their positive
+/// mirror images. `z` is then the analytic signal, which circles around while its real part
+/// traces the signal, and it stays smooth between the samples.
+pub fn fourier_coefficients(
+    spectrum: &[Complex<f64>],
+    input_signal_range: f64,
+    count: usize,
+) -> Vec<(f64, Complex<f64>)> {
+    let n = spectrum.len();
+    let mut terms = spectrum
+        .iter()
+        .enumerate()
+        .take(n / 2 + 1)
+        .map(|(k, x)| {
+            // DC and Nyquist have no mirror image.
+            let weight = if k == 0 || 2 * k == n { 1.0 } else { 2.0 };
+            (
+                bin_to_frequency(k, n, input_signal_range),
+                x * weight / n as f64,
+            )
+        })
+        .collect::<Vec<_>>();
+    terms.sort_by(|a, b| b.1.norm().total_cmp(&a.1.norm()));
+    terms.truncate(count);
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    use crate::{
+        app::{get_combined_wave, InputData, PeriodicFunction},
+        fft::{fft, ifft},
+    };
+
+    const NUM_SAMPLES: usize = 1024;
+
+    #[test]
+    fn bins_map_to_frequencies() {
+        // 1000 samples over 2 s are sampled at 500 Hz, so the bins are 0.5 Hz apart.
+        assert_eq!(frequency_resolution(2.0), 0.5);
+        assert_eq!(bin_to_frequency(0, 1000, 2.0), 0.0);
+        assert_eq!(bin_to_frequency(10, 1000, 2.0), 5.0);
+        assert_eq!(bin_to_frequency(500, 1000, 2.0), 250.0);
+        assert_eq!(bin_to_frequency(990, 1000, 2.0), -5.0);
+
+        // A 3 Hz cosine peaks at 3 Hz in the one-sided spectrum.
+        let wave = get_combined_wave(
+            vec![InputData::tone(PeriodicFunction::Cos, 1.0, 3.0)],
+            1000,
+            2.0,
+        );
+        let mut spectrum = wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+        let half = one_sided(&spectrum);
+        assert_eq!(half.len(), 500);
+        let peak = (0..half.len())
+            .max_by(|a, b| half[*a].norm().total_cmp(&half[*b].norm()))
+            .unwrap();
+        assert_eq!(bin_to_frequency(peak, spectrum.len(), 2.0), 3.0);
+    }
+
+    #[test]
+    fn band_mask_reconstructs_the_tone_inside_the_band() {
+        let functions = vec![
+            InputData::tone(PeriodicFunction::Sin, 1.0, 3.0),
+            InputData::tone(PeriodicFunction::Sin, 0.5, 40.0),
+        ];
+        let wave = get_combined_wave(functions, NUM_SAMPLES, 1.0);
+        let mut spectrum = wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+
+        // Over a range of 1 s, bin k is k Hz, so 40 Hz lies well outside a band up to 5 Hz.
+        apply_band_mask(&mut spectrum, 1.0, (0.0, 5.0));
+        let mut reconstruction = spectrum;
+        ifft(&mut reconstruction);
+        for ((x, _), y) in wave.iter().zip(&reconstruction) {
+            assert!((y.re - (TAU * 3.0 * x).sin()).abs() < 1e-9);
+            assert!(y.im.abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn fourier_series_retraces_the_samples() {
+        let functions = vec![
+            InputData::tone(PeriodicFunction::Cos, 1.0, 3.0),
+            InputData::tone(PeriodicFunction::Square, 0.5, 2.0),
+        ];
+        let wave = get_combined_wave(functions, 64, 1.0);
+        let mut spectrum = wave.iter().map(|(_, y)| *y).collect::<Vec<_>>();
+        fft(&mut spectrum);
+
+        // The cosine has the largest amplitude, so it is the strongest term.
+        let strongest = fourier_coefficients(&spectrum, 1.0, 1);
+        assert!((strongest[0].0 - 3.0).abs() < 1e-9);
+        assert!((strongest[0].1 - 1.0).norm() < 1e-9);
+
+        let terms = fourier_coefficients(&spectrum, 1.0, spectrum.len());
+        for (x, y) in &wave {
+            let sum = terms
+                .iter()
+                .map(|(f, c)| c * Complex::from_polar(1.0, TAU * f * x))
+                .sum::<Complex<f64>>();
+            assert!((sum.re - y.re).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::f64::consts::PI;
+
+use num_complex::Complex;
+
+use crate::{analysis::pick_peaks, fft::fft, window::WindowFunction};
+
+/// Short-time Fourier transform with a Hann window.
+///
+/// `frame_size` is rounded up to the next power of two. Only frames that fit completely into
+/// the signal are returned.
+pub fn stft(signal: &[f64], frame_size: usize, hop_size: usize) -> Vec<Vec<Complex<f64>>> {
+    windowed_stft(signal, frame_size, hop_size, WindowFunction::Hann)
+}
+
+/// Like [`stft`], but with the given window function instead of the Hann window.
+pub fn windowed_stft(
+    signal: &[f64],
+    frame_size: usize,
+    hop_size: usize,
+    window: WindowFunction,
+) -> Vec<Vec<Complex<f64>>> {
+    let frame_size = frame_size.max(2).next_power_of_two();
+    stft_with_window(signal, &window.periodic_coefficients(frame_size), hop_size)
+}
+
+/// Short-time Fourier transform with frames as long as `window`.
+fn stft_with_window(signal: &[f64], window: &[f64], hop_size: usize) -> Vec<Vec<Complex<f64>>> {
+    let frame_size = window.len();
+    if signal.len() < frame_size {
+        return Vec::new();
+    }
+
+    (0..=signal.len() - frame_size)
+        .step_by(hop_size.max(1))
+        .map(|start| {
+            let mut frame = signal[start..start + frame_size]
+                .iter()
+                .zip(window.iter())
+                .map(|(x, w)| Complex::from(x * w))
+                .collect::<Vec<_>>();
+            fft(&mut frame);
+            frame
+        })
+        .collect()
+}
+
+/// The three STFTs [`spectral_reassignment`] needs: with the Hann window, with its time
+/// derivative and with the window multiplied by the time from the frame center, all in samples.
+pub fn reassignment_stfts(
+    signal: &[f64],
+    frame_size: usize,
+    hop_size: usize,
+) -> [Vec<Vec<Complex<f64>>>; 3] {
+    let frame_size = frame_size.max(2).next_power_of_two();
+    let window = WindowFunction::Hann.periodic_coefficients(frame_size);
+    let omega = 2.0 * PI / frame_size as f64;
+    let derivative = (0..frame_size)
+        .map(|n| 0.5 * omega * (omega * n as f64).sin())
+        .collect::<Vec<_>>();
+    let center = frame_size as f64 / 2.0;
+    let time_ramped = window
+        .iter()
+        .enumerate()
+        .map(|(n, w)| (n as f64 - center) * w)
+        .collect::<Vec<_>>();
+
+    [
+        stft_with_window(signal, &window, hop_size),
+        stft_with_window(signal, &derivative, hop_size),
+        stft_with_window(signal, &time_ramped, hop_size),
+    ]
+}
+
+/// Moves the energy of every STFT bin to the center of gravity of the bin's energy.
+///
+/// `stft_frames_time_deriv` comes from the derivative of the analysis window and corrects the
+/// frequency, `stft_frames_freq_deriv` from the window multiplied by time, which is the
+/// frequency derivative of the STFT, and corrects the time. Returns
+/// `(time in samples, frequency, magnitude)` for every bin up to Nyquist with any energy.
+pub fn spectral_reassignment(
+    stft_frames_signal: &[Vec<Complex<f64>>],
+    stft_frames_time_deriv: &[Vec<Complex<f64>>],
+    stft_frames_freq_deriv: &[Vec<Complex<f64>>],
+    freq_resolution: f64,
+    hop_size: usize,
+) -> Vec<(f64, f64, f64)> {
+    let mut points = Vec::new();
+    for (frame, ((signal, time_deriv), freq_deriv)) in stft_frames_signal
+        .iter()
+        .zip(stft_frames_time_deriv)
+        .zip(stft_frames_freq_deriv)
+        .enumerate()
+    {
+        let frame_size = signal.len();
+        let sample_rate = freq_resolution * frame_size as f64;
+        let center = (frame * hop_size) as f64 + frame_size as f64 / 2.0;
+
+        for k in 0..frame_size / 2 {
+            let power = signal[k].norm_sqr();
+            if power == 0.0 {
+                continue;
+            }
+            let time = center + (freq_deriv[k] * signal[k].conj()).re / power;
+            let frequency = k as f64 * freq_resolution
+                - (time_deriv[k] * signal[k].conj()).im / power * sample_rate / (2.0 * PI);
+            points.push((time, frequency, power.sqrt()));
+        }
+    }
+    points
+}
+
+/// Follows spectral peaks across STFT frames.
+///
+/// A peak continues the track whose last peak in the previous frame is within
+/// `max_peak_gap_bins`, otherwise it starts a new track. Tracks end in the first frame without a
+/// nearby peak. Each track is a list of `(time, frequency)` points, where the time is the center
+/// of the frame in seconds.
+pub fn track_spectral_peaks(
+    stft_frames: &[Vec<Complex<f64>>],
+    freq_resolution: f64,
+    hop_size: usize,
+    max_peak_gap_bins: usize,
+) -> Vec<Vec<(f64, f64)>> {
+    let magnitudes = stft_frames
+        .iter()
+        .map(|frame| {
+            frame[..frame.len() / 2]
+                .iter()
+                .map(|bin| bin.norm())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let threshold = 0.1 * magnitudes.iter().flatten().copied().fold(0.0, f64::max);
+
+    let mut finished = Vec::new();
+    // Active tracks together with the bin of their most recent peak.
+    let mut active: Vec<(usize, Vec<(f64, f64)>)> = Vec::new();
+    for (frame, (magnitudes, bins)) in magnitudes.iter().zip(stft_frames).enumerate() {
+        let sample_rate = freq_resolution * bins.len() as f64;
+        let time = ((frame * hop_size) as f64 + bins.len() as f64 / 2.0) / sample_rate;
+        let mut continued = Vec::new();
+        for peak in pick_peaks(magnitudes, threshold) {
+            let point = (time, peak as f64 * freq_resolution);
+            let nearest = active
+                .iter()
+                .enumerate()
+                .filter(|(_, (bin, _))| bin.abs_diff(peak) <= max_peak_gap_bins)
+                .min_by_key(|(_, (bin, _))| bin.abs_diff(peak))
+                .map(|(index, _)| index);
+
+            match nearest {
+                Some(index) => {
+                    let (_, mut track) = active.swap_remove(index);
+                    track.push(point);
+                    continued.push((peak, track));
+                }
+                None => continued.push((peak, vec![point])),
+            }
+        }
+        finished.extend(active.drain(..).map(|(_, track)| track));
+        active = continued;
+    }
+    finished.extend(active.into_iter().map(|(_, track)| track));
+
+    finished
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    #[test]
+    fn reassignment_sharpens_tone_between_bins() {
+        // 10.3 cycles per 64-sample frame falls between bins 10 and 11.
+        let frame_size = 64;
+        let signal = (0..512)
+            .map(|n| (TAU * 10.3 * n as f64 / frame_size as f64).cos())
+            .collect::<Vec<_>>();
+        let [frames, time_deriv, freq_deriv] = reassignment_stfts(&signal, frame_size, 16);
+        let points = spectral_reassignment(&frames, &time_deriv, &freq_deriv, 1.0, 16);
+
+        let max = points.iter().map(|(_, _, m)| *m).fold(0.0, f64::max);
+        for (_, frequency, magnitude) in points.iter().filter(|(_, _, m)| *m > 0.5 * max) {
+            assert!(
+                (frequency - 10.3).abs() < 0.05,
+                "{frequency} at {magnitude}"
+            );
+        }
+    }
+
+    #[test]
+    fn windowed_stft_of_bin_centered_tone() {
+        let samples = (0..256)
+            .map(|n| (TAU * 8.0 * n as f64 / 64.0).cos())
+            .collect::<Vec<_>>();
+        let frames = windowed_stft(&samples, 64, 16, WindowFunction::Rectangular);
+        assert_eq!(frames.len(), (256 - 64) / 16 + 1);
+        for frame in &frames {
+            assert_eq!(frame.len(), 64);
+            assert!((frame[8].norm() - 32.0).abs() < 1e-9);
+            assert!(frame[9].norm() < 1e-9);
+        }
+
+        // Tapered windows leak into the neighbouring bins instead.
+        let frames = windowed_stft(&samples, 64, 16, WindowFunction::Blackman);
+        assert!(frames[0][9].norm() > 1.0);
+        assert!(frames[0][12].norm() < 1e-3 * frames[0][8].norm());
+    }
+}
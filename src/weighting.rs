@@ -0,0 +1,124 @@
+use std::fmt;
+
+use num_complex::Complex;
+
+/// Frequency weighting curves from IEC 61672 (A, B, C), IEC 537 (D) and ITU-R 468.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum FrequencyWeighting {
+    #[default]
+    None,
+    A,
+    B,
+    C,
+    D,
+    Itu468,
+}
+
+impl FrequencyWeighting {
+    pub const ALL: [FrequencyWeighting; 6] = [
+        FrequencyWeighting::None,
+        FrequencyWeighting::A,
+        FrequencyWeighting::B,
+        FrequencyWeighting::C,
+        FrequencyWeighting::D,
+        FrequencyWeighting::Itu468,
+    ];
+
+    /// Unit of a level measured with this weighting.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            FrequencyWeighting::None => "dB",
+            FrequencyWeighting::A => "dB(A)",
+            FrequencyWeighting::B => "dB(B)",
+            FrequencyWeighting::C => "dB(C)",
+            FrequencyWeighting::D => "dB(D)",
+            FrequencyWeighting::Itu468 => "dB(468)",
+        }
+    }
+
+    /// Linear gain of the weighting at `frequency` Hz.
+    pub fn gain(&self, frequency: f64) -> f64 {
+        let f = frequency.abs();
+        let f2 = f * f;
+        let db_to_linear = |db: f64| 10f64.powf(db / 20.0);
+
+        match self {
+            FrequencyWeighting::None => 1.0,
+            FrequencyWeighting::A => {
+                12194f64.powi(2) * f2 * f2
+                    / ((f2 + 20.6f64.powi(2))
+                        * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+                        * (f2 + 12194f64.powi(2)))
+                    * db_to_linear(2.0)
+            }
+            FrequencyWeighting::B => {
+                12194f64.powi(2) * f2 * f
+                    / ((f2 + 20.6f64.powi(2))
+                        * (f2 + 158.5f64.powi(2)).sqrt()
+                        * (f2 + 12194f64.powi(2)))
+                    * db_to_linear(0.17)
+            }
+            FrequencyWeighting::C => {
+                12194f64.powi(2) * f2 / ((f2 + 20.6f64.powi(2)) * (f2 + 12194f64.powi(2)))
+                    * db_to_linear(0.06)
+            }
+            FrequencyWeighting::D => {
+                let h = ((1037918.48 - f2).powi(2) + 1080768.16 * f2)
+                    / ((9837328.0 - f2).powi(2) + 11723776.0 * f2);
+                f / 6.8966888496476e-5 * (h / ((f2 + 79919.29) * (f2 + 1345600.0))).sqrt()
+            }
+            FrequencyWeighting::Itu468 => {
+                let h1 = -4.737338981378384e-24 * f2.powi(3) + 2.043828333606125e-15 * f2 * f2
+                    - 1.363894795463638e-7 * f2
+                    + 1.0;
+                let h2 = 1.306612257412824e-19 * f2 * f2 * f - 2.118150887518656e-11 * f2 * f
+                    + 5.559488023498642e-4 * f;
+                1.246332637532143e-4 * f / (h1 * h1 + h2 * h2).sqrt() * db_to_linear(18.2)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FrequencyWeighting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrequencyWeighting::None => write!(f, "None"),
+            FrequencyWeighting::A => write!(f, "A"),
+            FrequencyWeighting::B => write!(f, "B"),
+            FrequencyWeighting::C => write!(f, "C"),
+            FrequencyWeighting::D => write!(f, "D"),
+            FrequencyWeighting::Itu468 => write!(f, "ITU-R 468"),
+        }
+    }
+}
+
+/// Applies the weighting to a full FFT output, treating bin `k` and `N - k` as the same
+/// frequency.
+pub fn apply_frequency_weighting(
+    bins: &[Complex<f64>],
+    weighting: FrequencyWeighting,
+    freq_resolution: f64,
+) -> Vec<Complex<f64>> {
+    let n = bins.len();
+    bins.iter()
+        .enumerate()
+        .map(|(k, bin)| bin * weighting.gain(k.min(n - k) as f64 * freq_resolution))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_weighting_is_unity_at_1_khz() {
+        let gain_db = 20.0 * FrequencyWeighting::A.gain(1000.0).log10();
+        assert!(gain_db.abs() < 0.01, "A-weighting at 1 kHz is {gain_db} dB");
+
+        // Bin 10 of a 0.01 s signal is 1 kHz, so it passes through unchanged.
+        let bins = vec![Complex::new(1.0, 0.0); 32];
+        let weighted = apply_frequency_weighting(&bins, FrequencyWeighting::A, 100.0);
+        assert!((weighted[10].norm() - 1.0).abs() < 1e-3);
+        assert_eq!(weighted[0].norm(), 0.0);
+    }
+}